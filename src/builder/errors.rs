@@ -1,18 +1,18 @@
 use std::{error::Error, fmt};
 
-use oxc_diagnostics::OxcDiagnostic;
+use oxc_diagnostics::GraphicalReportHandler;
 
-// Custom error type to hold `Vec<OxcDiagnostic>`
+// Custom error type to hold diagnostics with their module sources attached
 #[derive(Debug)]
 pub struct DiagnosticsError {
-    pub diagnostics: Vec<OxcDiagnostic>,
+    pub diagnostics: Vec<oxc_diagnostics::Error>,
 }
 
 // Implement `Display` for `DiagnosticsError`
 impl fmt::Display for DiagnosticsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for diagnostic in &self.diagnostics {
-            writeln!(f, "{}", diagnostic)?;
+            write!(f, "{}", render_diagnostic(diagnostic))?;
         }
         Ok(())
     }
@@ -20,3 +20,13 @@ impl fmt::Display for DiagnosticsError {
 
 // Implement `Error` for `DiagnosticsError`
 impl Error for DiagnosticsError {}
+
+/// Renders a diagnostic with oxc's graphical reporter,
+/// including the labelled source snippet if the source is attached.
+pub fn render_diagnostic(diagnostic: &oxc_diagnostics::Error) -> String {
+    let mut output = String::new();
+    GraphicalReportHandler::new()
+        .render_report(&mut output, &**diagnostic)
+        .expect("Writing to a String cannot fail");
+    output
+}
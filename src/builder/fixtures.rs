@@ -0,0 +1,139 @@
+//! Helpers to convert inline TypeScript fixtures in tests.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::rs_types::*;
+
+use super::{options::TypeScriptOptions, TypeScriptToRustBuilder};
+
+pub(super) const STRING: RSType = RSType::Primitive(RSPrimitive::String);
+pub(super) const NUMBER: RSType = RSType::Primitive(RSPrimitive::F64);
+pub(super) const BOOLEAN: RSType = RSType::Primitive(RSPrimitive::Bool);
+
+/// The files of a test, written to a directory of its own so that tests can run in parallel.
+pub(super) struct Fixture {
+    pub(super) directory: PathBuf,
+}
+
+impl Fixture {
+    /// Writes the files, as paths relative to the fixture directory and their contents.
+    pub(super) fn new(test_name: &str, files: &[(&str, &str)]) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("ts2rs-oxc-{}-{}", std::process::id(), test_name));
+        let _ = fs::remove_dir_all(&directory);
+        for (path, contents) in files {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        Self {
+            directory: directory.canonicalize().unwrap(),
+        }
+    }
+
+    /// Returns the path of a fixture file.
+    pub(super) fn path(&self, path: &str) -> PathBuf {
+        self.directory.join(path)
+    }
+
+    /// Visits the entry modules, and returns the builder with the error of `visit_modules`.
+    pub(super) fn visit(
+        &self,
+        entrypoints: &[&str],
+        options: TypeScriptOptions,
+    ) -> (TypeScriptToRustBuilder, Result<(), String>) {
+        let mut builder = TypeScriptToRustBuilder::new(options);
+        let result = builder
+            .visit_modules(entrypoints.iter().map(|path| self.path(path)))
+            .map_err(|error| error.to_string());
+        (builder, result)
+    }
+
+    /// Visits the entry module with the default options, which must succeed.
+    pub(super) fn convert(&self, entrypoint: &str) -> TypeScriptToRustBuilder {
+        let (builder, result) = self.visit(&[entrypoint], TypeScriptOptions::default());
+        result.expect("the module is converted");
+        builder
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+/// Converts a single inline module, which may only declare local types, and returns its
+/// resolved types.
+pub(super) fn resolve(test_name: &str, source: &str) -> HashMap<String, RSType> {
+    let source = format!("{}\nexport {{}};\n", source);
+    let fixture = Fixture::new(test_name, &[("index.ts", &source)]);
+    let builder = fixture.convert("index.ts");
+    types(&builder, &fixture.path("index.ts")).clone()
+}
+
+/// Returns the resolved types of a visited module.
+pub(super) fn types<'b>(
+    builder: &'b TypeScriptToRustBuilder,
+    module_path: &Path,
+) -> &'b HashMap<String, RSType> {
+    &builder.modules[module_path].types
+}
+
+/// Returns the messages of the lossy conversions found, in the order they were found.
+pub(super) fn warnings(builder: &TypeScriptToRustBuilder) -> Vec<String> {
+    builder
+        .warnings()
+        .iter()
+        .map(|warning| warning.to_diagnostic().to_string())
+        .collect()
+}
+
+/// Returns the messages of the recoverable problems found, like unresolvable imports.
+pub(super) fn diagnostics(builder: &TypeScriptToRustBuilder) -> Vec<String> {
+    builder
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect()
+}
+
+pub(super) fn string_literal(value: &str) -> RSType {
+    RSType::EnumVariant(RSEnumVariant::StringLiteral(value.to_string()))
+}
+
+pub(super) fn numeric_literal(value: &str) -> RSType {
+    RSType::EnumVariant(RSEnumVariant::NumericLiteral(value.to_string()))
+}
+
+pub(super) fn union(variants: Vec<RSType>) -> RSType {
+    RSType::Enum(RSEnum {
+        option: false,
+        variants,
+    })
+}
+
+pub(super) fn object(fields: Vec<(&str, RSType)>) -> RSType {
+    RSType::Struct(RSStruct {
+        fields: fields
+            .into_iter()
+            .map(|(name, field_type)| (name.to_string(), field_type))
+            .collect(),
+    })
+}
+
+pub(super) fn option(inner: RSType) -> RSType {
+    RSType::Option(Box::new(inner))
+}
+
+/// A reference resolved to the type `name` declared in the module `module_path`.
+pub(super) fn resolved(name: &str, module_path: &Path) -> RSType {
+    RSType::Reference(RSReference::Resolved {
+        name: name.to_string(),
+        module_path: module_path.to_path_buf(),
+    })
+}
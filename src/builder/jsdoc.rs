@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use oxc_allocator::Allocator;
+use oxc_ast::{ast::Statement, Trivias};
+use oxc_parser::Parser;
+use oxc_span::{SourceType, Span};
+use tracing::debug;
+
+use crate::rs_types::{RSStruct, RSType};
+
+use super::{make_rs_type::make_field_type, warnings::Fallback, TypeScriptToRustVisitor};
+
+/// A block tag of a JSDoc comment, e.g. `@property {string} [bar] The bar.`
+#[derive(Debug)]
+struct JSDocTag<'s> {
+    /// The tag name without `@`, e.g. `property`.
+    name: &'s str,
+    /// The type expression between the braces and its span in the module source.
+    type_expression: Option<(&'s str, Span)>,
+    /// The text after the type expression, starting with the name of the typedef or property.
+    text: &'s str,
+    /// The span of the tag in the module source.
+    span: Span,
+}
+
+/// A `@typedef` being converted, whose struct gets the fields of the following `@property` tags.
+struct Typedef {
+    name: String,
+    rs_type: RSType,
+}
+
+impl TypeScriptToRustVisitor {
+    /// Converts the `@typedef` and `@property` tags of the JSDoc comments in a JavaScript module.
+    pub(super) fn visit_jsdoc_typedefs(&mut self, trivias: &Trivias) {
+        let source_text = self.source_text.clone();
+        for comment in trivias.comments().filter(|comment| comment.is_block()) {
+            let text = comment.span.source_text(&source_text);
+            if !text.starts_with('*') {
+                continue;
+            }
+
+            let mut typedef: Option<Typedef> = None;
+            for tag in parse_tags(text, comment.span.start) {
+                match tag.name {
+                    "typedef" => {
+                        if let Some(typedef) = typedef.take() {
+                            self.declare_typedef(typedef);
+                        }
+                        typedef = self.make_typedef(&tag);
+                    }
+                    "property" | "prop" => match &mut typedef {
+                        Some(typedef) => self.add_property(typedef, &tag),
+                        None => debug!("@{} outside of a @typedef", tag.name),
+                    },
+                    _ => {}
+                }
+            }
+            if let Some(typedef) = typedef {
+                self.declare_typedef(typedef);
+            }
+        }
+    }
+
+    /// `@typedef {Object} Foo` starts a struct, and `@typedef {'a'|'b'} Kind` is a type alias.
+    fn make_typedef(&mut self, tag: &JSDocTag) -> Option<Typedef> {
+        let name = tag.text.split_whitespace().next()?.to_string();
+        debug!("TYPEDEF: {}", name);
+        let rs_type = match tag.type_expression {
+            Some((type_expression, span))
+                if !matches!(type_expression.trim(), "Object" | "object") =>
+            {
+                self.make_jsdoc_type(type_expression, span)
+            }
+            _ => RSType::Struct(RSStruct {
+                fields: HashMap::new(),
+            }),
+        };
+        Some(Typedef { name, rs_type })
+    }
+
+    /// `@property {string} bar` and `@property {string} [bar]` add a field to a struct typedef.
+    fn add_property(&mut self, typedef: &mut Typedef, tag: &JSDocTag) {
+        let RSType::Struct(RSStruct { fields }) = &mut typedef.rs_type else {
+            debug!("@{} of the non-object typedef {}", tag.name, typedef.name);
+            return;
+        };
+        let Some(name) = tag.text.split_whitespace().next() else {
+            return;
+        };
+        // `[bar]` and `[bar=default]` are optional.
+        let (name, optional) = match name.strip_prefix('[') {
+            Some(name) => (name.split([']', '=']).next().unwrap_or(name), true),
+            None => (name, false),
+        };
+        if name.contains('.') {
+            let what = format!("nested property `{}.{}`", typedef.name, name);
+            self.warn(tag, tag.span, Fallback::Dropped(what));
+            return;
+        }
+        let Some((type_expression, span)) = tag.type_expression else {
+            let what = format!("untyped field `{}.{}`", typedef.name, name);
+            self.warn(tag, tag.span, Fallback::Dropped(what));
+            return;
+        };
+
+        let warnings_before = self.warnings.len();
+        let rs_type = self.make_jsdoc_type(type_expression, span);
+        let rs_type = make_field_type(rs_type, optional);
+        let what = format!("field `{}.{}`", typedef.name, name);
+        if self.drop_unimplemented_field(&rs_type, warnings_before, &what) {
+            return;
+        }
+        fields.insert(name.to_string(), rs_type);
+    }
+
+    fn declare_typedef(&mut self, typedef: Typedef) {
+        match &typedef.rs_type {
+            RSType::Struct(RSStruct { fields }) if fields.is_empty() => {
+                debug!("typedef {} has no properties", typedef.name);
+            }
+            _ => self.declare_type(typedef.name, typedef.rs_type),
+        }
+    }
+
+    /// Parses a JSDoc type expression as a TypeScript type and converts it with `make_rs_type`.
+    /// The expression is parsed on its own, and the spans of its nodes are shifted to its offset
+    /// in the module source.
+    fn make_jsdoc_type(&mut self, type_expression: &str, span: Span) -> RSType {
+        // `*` and `?` are the JSDoc any and unknown types.
+        if matches!(type_expression.trim(), "*" | "?") {
+            return RSType::JSONValue;
+        }
+
+        const PREFIX: &str = "type T=";
+        let source_text = format!("{}{}", PREFIX, normalize_type_expression(type_expression));
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, &source_text, SourceType::ts()).parse();
+
+        match ret.program.body.first() {
+            Some(Statement::TSTypeAliasDeclaration(alias)) if ret.errors.is_empty() => {
+                // The nodes of the expression start after the prefix.
+                self.span_offset = span.start.wrapping_sub(PREFIX.len() as u32);
+                let rs_type = self.make_rs_type(&alias.type_annotation);
+                self.span_offset = 0;
+                rs_type
+            }
+            // Types that are not TypeScript types, e.g. `function(string): void`.
+            _ => {
+                let construct = "JSDocType".to_string();
+                self.warn_construct(construct.clone(), span, Fallback::Unimplemented);
+                RSType::Unimplemented(construct, type_expression.to_string(), span)
+            }
+        }
+    }
+}
+
+/// Splits the text of a JSDoc comment (starting at `offset` in the module source) into its tags.
+fn parse_tags(text: &str, offset: u32) -> Vec<JSDocTag<'_>> {
+    // Tags start a line, after the leading `*`.
+    let mut starts: Vec<usize> = Vec::new();
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_start().trim_start_matches('*').trim_start();
+        if content.starts_with('@') {
+            starts.push(line_start + line.len() - content.len());
+        }
+        line_start += line.len();
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(text.len());
+            parse_tag(&text[start..end], offset + start as u32)
+        })
+        .collect()
+}
+
+/// Parses a tag like `@property {string} [bar] The bar.` starting at `offset` in the module source.
+fn parse_tag(text: &str, offset: u32) -> Option<JSDocTag<'_>> {
+    let rest = text.strip_prefix('@')?;
+    let name_end = rest
+        .find(|c: char| c.is_whitespace() || c == '{')
+        .unwrap_or(rest.len());
+    let name = &rest[..name_end];
+    let mut text_start = text.len() - rest[name_end..].trim_start().len();
+
+    let type_expression = match text[text_start..].starts_with('{') {
+        true => {
+            let mut depth = 0;
+            let end = text[text_start..].char_indices().find_map(|(i, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                (depth == 0).then_some(text_start + i)
+            })?;
+            let type_start = text_start + 1;
+            text_start = end + 1;
+            Some((
+                &text[type_start..end],
+                Span::new(offset + type_start as u32, offset + end as u32),
+            ))
+        }
+        false => None,
+    };
+
+    let tag_end = text
+        .trim_end_matches(|c: char| c.is_whitespace() || c == '*')
+        .len();
+    Some(JSDocTag {
+        name,
+        type_expression,
+        text: text[text_start..].trim_start(),
+        span: Span::new(offset, offset + tag_end as u32),
+    })
+}
+
+/// Rewrites JSDoc-only syntax without moving the rest of the expression:
+/// the leading `*` of continuation lines and `Array.<string>` generics.
+fn normalize_type_expression(type_expression: &str) -> String {
+    let mut normalized = String::with_capacity(type_expression.len());
+    let mut line_start = false;
+    for c in type_expression.chars() {
+        match c {
+            '\n' => line_start = true,
+            '*' if line_start => {
+                normalized.push(' ');
+                line_start = false;
+                continue;
+            }
+            c if c.is_whitespace() => {}
+            _ => line_start = false,
+        }
+        normalized.push(c);
+    }
+    normalized.replace(".<", " <")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::super::{fixtures::*, options::TypeScriptOptions};
+    use crate::rs_types::*;
+
+    #[test]
+    fn typedefs_and_properties_are_converted() {
+        let fixture = Fixture::new(
+            "jsdoc_typedefs",
+            &[(
+                "index.js",
+                r#"
+/**
+ * @typedef {'a'|'b'} Kind
+ */
+
+/**
+ * @typedef {Object} Options
+ * @property {string} name
+ * @property {Kind} [kind] The kind.
+ * @property {Array.<number>} sizes
+ * @property {?string} maybe
+ * @property {!string} sure
+ * @property {?} anything
+ * @property {() => void} callback
+ * @property {function(string): void} listener
+ */
+export {};
+"#,
+            )],
+        );
+        let builder = fixture.convert("index.js");
+        let module_path = fixture.path("index.js");
+        let types = types(&builder, &module_path);
+        assert_eq!(
+            types["Kind"],
+            union(vec![string_literal("a"), string_literal("b")])
+        );
+        assert_eq!(
+            types["Options"],
+            object(vec![
+                ("name", STRING),
+                ("kind", option(resolved("Kind", &module_path))),
+                ("sizes", RSType::Vec(Box::new(NUMBER))),
+                ("maybe", option(STRING)),
+                ("sure", STRING),
+                ("anything", RSType::JSONValue),
+            ])
+        );
+        // Unimplemented properties are dropped like fields of interfaces.
+        // Types that cannot be parsed are unimplemented.
+        assert_eq!(
+            warnings(&builder),
+            [
+                "`TSFunctionType` dropped: field `Options.callback`",
+                "`JSDocType` dropped: field `Options.listener`",
+            ]
+        );
+        let source = fs::read_to_string(&module_path).unwrap();
+        assert_eq!(
+            builder.warnings()[1].span.source_text(&source),
+            "function(string): void"
+        );
+
+        let options = TypeScriptOptions {
+            strict: true,
+            ..TypeScriptOptions::default()
+        };
+        let (_, result) = fixture.visit(&["index.js"], options);
+        assert!(result
+            .expect_err("strict mode fails")
+            .contains("`JSDocType` dropped: field `Options.listener`"));
+    }
+}
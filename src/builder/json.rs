@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::rs_types::{RSEnum, RSPrimitive, RSStruct, RSType};
+
+use super::make_rs_type::make_field_type;
+
+/// Infers the type of a JSON document, like TypeScript does for JSON modules:
+/// objects become structs, and arrays a `Vec` of the unified type of their elements.
+pub(super) fn infer_json_type(value: &Value) -> RSType {
+    match value {
+        Value::Null => RSType::NullOrUndefined,
+        Value::Bool(_) => RSType::Primitive(RSPrimitive::Bool),
+        Value::Number(number) if number.is_i64() => RSType::Primitive(RSPrimitive::I64),
+        Value::Number(_) => RSType::Primitive(RSPrimitive::F64),
+        Value::String(_) => RSType::Primitive(RSPrimitive::String),
+        Value::Array(elements) => {
+            let element_type = elements
+                .iter()
+                .map(infer_json_type)
+                .reduce(unify_types)
+                .unwrap_or(RSType::JSONValue);
+            RSType::Vec(Box::new(element_type))
+        }
+        Value::Object(object) => RSType::Struct(RSStruct {
+            fields: object
+                .iter()
+                .map(|(key, value)| (key.clone(), infer_json_type(value)))
+                .collect(),
+        }),
+    }
+}
+
+/// Returns a type that can hold the values of both types.
+fn unify_types(a: RSType, b: RSType) -> RSType {
+    match (a, b) {
+        (a, b) if a == b => a,
+        (RSType::Primitive(RSPrimitive::I64), RSType::Primitive(RSPrimitive::F64))
+        | (RSType::Primitive(RSPrimitive::F64), RSType::Primitive(RSPrimitive::I64)) => {
+            RSType::Primitive(RSPrimitive::F64)
+        }
+        (RSType::NullOrUndefined, RSType::Option(inner))
+        | (RSType::Option(inner), RSType::NullOrUndefined) => RSType::Option(inner),
+        (RSType::NullOrUndefined, other) | (other, RSType::NullOrUndefined) => {
+            RSType::Option(Box::new(other))
+        }
+        (RSType::Option(a), RSType::Option(b)) => RSType::Option(Box::new(unify_types(*a, *b))),
+        (RSType::Option(a), b) | (b, RSType::Option(a)) => {
+            RSType::Option(Box::new(unify_types(*a, b)))
+        }
+        (RSType::Vec(a), RSType::Vec(b)) => RSType::Vec(Box::new(unify_types(*a, *b))),
+        // Fields missing from some of the objects are optional.
+        (RSType::Struct(a), RSType::Struct(mut b)) => {
+            let mut fields: HashMap<String, RSType> = HashMap::new();
+            for (name, a_type) in a.fields {
+                let field_type = match b.fields.remove(&name) {
+                    Some(b_type) => unify_types(a_type, b_type),
+                    None => make_field_type(a_type, true),
+                };
+                fields.insert(name, field_type);
+            }
+            for (name, b_type) in b.fields {
+                fields.insert(name, make_field_type(b_type, true));
+            }
+            RSType::Struct(RSStruct { fields })
+        }
+        // Anything else is a union of the distinct types.
+        (a, b) => {
+            let mut variants: Vec<RSType> = Vec::new();
+            let flattened = [a, b].into_iter().flat_map(|rs_type| match rs_type {
+                RSType::Enum(RSEnum { variants, .. }) => variants,
+                rs_type => vec![rs_type],
+            });
+            for variant in flattened {
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+            RSType::Enum(RSEnum {
+                option: false,
+                variants,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::*;
+    use crate::rs_types::*;
+
+    #[test]
+    fn typeof_queries_of_json_modules_are_inferred() {
+        let fixture = Fixture::new(
+            "json_modules",
+            &[
+                (
+                    "config.json",
+                    r#"{
+                        "port": 8080,
+                        "ratio": 0.5,
+                        "name": "app",
+                        "tags": ["a", "b"],
+                        "servers": [{ "host": "a" }, { "host": "b", "tls": true }],
+                        "proxy": null
+                    }"#,
+                ),
+                (
+                    "index.ts",
+                    r#"
+                    import config from "./config.json";
+                    export type Config = typeof config;
+                    export type Port = (typeof config)["port"];
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("index.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["Config"],
+            object(vec![
+                ("port", RSType::Primitive(RSPrimitive::I64)),
+                ("ratio", NUMBER),
+                ("name", STRING),
+                ("tags", RSType::Vec(Box::new(STRING))),
+                (
+                    "servers",
+                    RSType::Vec(Box::new(object(vec![
+                        ("host", STRING),
+                        ("tls", option(BOOLEAN)),
+                    ])))
+                ),
+                ("proxy", RSType::NullOrUndefined),
+            ])
+        );
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["Port"],
+            RSType::Primitive(RSPrimitive::I64)
+        );
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn invalid_json_modules_fail_the_conversion() {
+        let fixture = Fixture::new(
+            "invalid_json_module",
+            &[
+                ("config.json", r#"{ "port": 8080, }"#),
+                (
+                    "index.ts",
+                    "import config from './config.json';\nexport type Config = typeof config;\n",
+                ),
+            ],
+        );
+        let (_, result) = fixture.visit(&["index.ts"], Default::default());
+        let error = result.expect_err("the conversion fails");
+        assert!(error.contains("Invalid JSON module"));
+        assert!(error.contains("config.json"));
+    }
+}
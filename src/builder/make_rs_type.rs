@@ -1,124 +1,616 @@
 use std::{any::type_name_of_val, collections::HashMap, path::PathBuf};
 
-use oxc_ast::ast::{TSLiteral, TSType};
-use oxc_span::Span;
+use oxc_ast::ast::{
+    Expression, ObjectPropertyKind, PropertyKey, TSImportType, TSLiteral, TSMappedType,
+    TSMappedTypeModifierOperator, TSSignature, TSTupleElement, TSType, TSTypeOperatorOperator,
+    TSTypeQuery, TSTypeQueryExprName,
+};
+use oxc_span::{GetSpan, Span};
 use serde::Serialize;
+use tracing::trace;
 
 use crate::rs_types::*;
 
-pub(crate) fn make_rs_type(
-    ts_type: &TSType,
-    source: &str,
-    // imported_types: &HashMap<String, (PathBuf, String)>,
-) -> RSType {
-    let rs_type = match ts_type {
-        TSType::TSAnyKeyword(_) => RSType::JSONValue,
-        TSType::TSBigIntKeyword(_) => RSType::Primitive(RSPrimitive::I128),
-        TSType::TSBooleanKeyword(_) => RSType::Primitive(RSPrimitive::Bool),
-        TSType::TSIntrinsicKeyword(value) => unimplemented_type(value, value.span, source),
-        TSType::TSNeverKeyword(_) => RSType::Unit,
-        TSType::TSNullKeyword(_) => RSType::NullOrUndefined,
-        TSType::TSNumberKeyword(_) => RSType::Primitive(RSPrimitive::F64),
-        TSType::TSObjectKeyword(_) => RSType::JSONValue,
-        TSType::TSStringKeyword(_) => RSType::Primitive(RSPrimitive::String),
-        TSType::TSSymbolKeyword(_) => RSType::Primitive(RSPrimitive::String),
-        TSType::TSUndefinedKeyword(_) => RSType::NullOrUndefined,
-        TSType::TSUnknownKeyword(_) => RSType::JSONValue,
-        TSType::TSVoidKeyword(_) => RSType::Unit,
-        TSType::TSArrayType(array) => {
-            let element_type = make_rs_type(&array.element_type, source);
-            RSType::Vec(Box::new(element_type))
-        }
-        TSType::TSConditionalType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSConstructorType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSFunctionType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSImportType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSIndexedAccessType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSInferType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSIntersectionType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSLiteralType(literal) => {
-            let variant = match &literal.literal {
-                TSLiteral::BooleanLiteral(boolean) => RSEnumVariant::BooleanLiteral(boolean.value),
-                TSLiteral::NullLiteral(_) => RSEnumVariant::NullLiteral,
-                TSLiteral::NumericLiteral(numeric) => {
-                    RSEnumVariant::NumericLiteral(numeric.raw.into())
+use super::{
+    warnings::{ConversionWarning, Fallback},
+    TypeScriptToRustVisitor,
+};
+
+impl TypeScriptToRustVisitor {
+    pub(crate) fn make_rs_type(
+        &mut self,
+        ts_type: &TSType,
+        // imported_types: &HashMap<String, (PathBuf, String)>,
+    ) -> RSType {
+        let rs_type = match ts_type {
+            TSType::TSAnyKeyword(value) => {
+                self.substituted_type(value, value.span, RSType::JSONValue)
+            }
+            TSType::TSBigIntKeyword(_) => RSType::Primitive(RSPrimitive::I128),
+            TSType::TSBooleanKeyword(_) => RSType::Primitive(RSPrimitive::Bool),
+            TSType::TSIntrinsicKeyword(value) => self.unimplemented_type(value, value.span),
+            TSType::TSNeverKeyword(_) => RSType::Never,
+            TSType::TSNullKeyword(_) => RSType::NullOrUndefined,
+            TSType::TSNumberKeyword(_) => RSType::Primitive(RSPrimitive::F64),
+            TSType::TSObjectKeyword(value) => {
+                self.substituted_type(value, value.span, RSType::Object)
+            }
+            TSType::TSStringKeyword(_) => RSType::Primitive(RSPrimitive::String),
+            TSType::TSSymbolKeyword(value) => {
+                self.substituted_type(value, value.span, RSType::Primitive(RSPrimitive::String))
+            }
+            TSType::TSUndefinedKeyword(_) => RSType::NullOrUndefined,
+            TSType::TSUnknownKeyword(_) => RSType::JSONValue,
+            TSType::TSVoidKeyword(_) => RSType::Unit,
+            TSType::TSArrayType(array) => {
+                let element_type = self.make_rs_type(&array.element_type);
+                RSType::Vec(Box::new(element_type))
+            }
+            TSType::TSConditionalType(conditional) => {
+                // `T extends U ? X : Y` distributes over a union passed as the type parameter `T`.
+                let distributive_parameter = match &conditional.check_type {
+                    TSType::TSTypeReference(reference) if reference.type_parameters.is_none() => {
+                        Some(reference.type_name.to_string())
+                            .filter(|name| self.type_parameters.contains(name))
+                    }
+                    _ => None,
+                };
+                RSType::Conditional(RSConditionalType {
+                    check_type: Box::new(self.make_rs_type(&conditional.check_type)),
+                    extends_type: Box::new(self.make_rs_type(&conditional.extends_type)),
+                    true_type: Box::new(self.make_rs_type(&conditional.true_type)),
+                    false_type: Box::new(self.make_rs_type(&conditional.false_type)),
+                    distributive_parameter,
+                    span: self.module_span(conditional.span),
+                })
+            }
+            TSType::TSConstructorType(value) => self.unimplemented_type(value, value.span),
+            TSType::TSFunctionType(value) => self.unimplemented_type(value, value.span),
+            TSType::TSImportType(import_type) => self.make_import_type(import_type),
+            TSType::TSIndexedAccessType(indexed_access) => RSType::IndexedAccess(
+                Box::new(self.make_rs_type(&indexed_access.object_type)),
+                Box::new(self.make_rs_type(&indexed_access.index_type)),
+                self.module_span(indexed_access.span),
+            ),
+            TSType::TSInferType(infer) => RSType::Infer(infer.type_parameter.name.name.to_string()),
+            TSType::TSIntersectionType(intersection) => RSType::Intersection(
+                self.make_rs_types(intersection.types.iter()),
+                self.module_span(intersection.span),
+            ),
+            TSType::TSLiteralType(literal) => {
+                let variant = match &literal.literal {
+                    TSLiteral::BooleanLiteral(boolean) => {
+                        RSEnumVariant::BooleanLiteral(boolean.value)
+                    }
+                    TSLiteral::NullLiteral(_) => RSEnumVariant::NullLiteral,
+                    TSLiteral::NumericLiteral(numeric) => {
+                        RSEnumVariant::NumericLiteral(numeric.raw.into())
+                    }
+                    TSLiteral::BigIntLiteral(bigint) => {
+                        RSEnumVariant::NumericLiteral(bigint.raw.clone().into_string())
+                    }
+                    TSLiteral::RegExpLiteral(value) => {
+                        self.unimplemented_variant(value, value.span)
+                    }
+                    TSLiteral::StringLiteral(string) => {
+                        RSEnumVariant::StringLiteral(string.value.clone().into_string())
+                    }
+                    // e.g. `abc` without substitutions
+                    TSLiteral::TemplateLiteral(template) => match template.quasi() {
+                        Some(quasi) => RSEnumVariant::StringLiteral(quasi.to_string()),
+                        None => self.unimplemented_variant(template, template.span),
+                    },
+                    TSLiteral::UnaryExpression(value) => {
+                        self.unimplemented_variant(value, value.span)
+                    }
+                };
+                RSType::EnumVariant(variant)
+            }
+            TSType::TSMappedType(mapped) => self.make_mapped_type(mapped),
+            TSType::TSNamedTupleMember(named_tuple_member) => {
+                // TODO: Make union type! Needs testing!
+                let element_ts_type = named_tuple_member.element_type.to_ts_type();
+                let element_type = self.make_rs_type(element_ts_type);
+                RSType::EnumVariant(RSEnumVariant::RSType(Box::new(element_type)))
+            }
+            TSType::TSQualifiedName(name) => {
+                // e.g. axe.Result, resolved through namespace imports and declarations
+                RSType::Reference(RSReference::Unresolved {
+                    name: name.to_string(),
+                    module_specifier: None,
+                })
+            }
+            TSType::TSTemplateLiteralType(template) => {
+                let quasis = template
+                    .quasis
+                    .iter()
+                    .map(|quasi| match &quasi.value.cooked {
+                        Some(cooked) => cooked.to_string(),
+                        None => quasi.value.raw.to_string(),
+                    })
+                    .collect();
+                let types = self.make_rs_types(template.types.iter());
+                RSType::TemplateLiteral(RSTemplateLiteral { quasis, types })
+            }
+            TSType::TSThisType(value) => self.unimplemented_type(value, value.span),
+            TSType::TSTupleType(tuple) => {
+                // e.g. [string, number] or [name: string, value: number]
+                let element_types = tuple
+                    .element_types
+                    .iter()
+                    .map(|element| match element {
+                        TSTupleElement::TSNamedTupleMember(member) if !member.optional => {
+                            let element = &member.element_type;
+                            element.is_ts_type().then(|| element.to_ts_type())
+                        }
+                        TSTupleElement::TSNamedTupleMember(_) => None,
+                        element => element.is_ts_type().then(|| element.to_ts_type()),
+                    })
+                    .collect::<Option<Vec<_>>>();
+                if let Some(element_types) = element_types {
+                    return RSType::Tuple(self.make_rs_types(element_types.into_iter()));
+                }
+
+                // Tuples with optional or rest elements are converted to arrays.
+                let variants: Vec<RSType> =
+                    self.make_rs_types(tuple.element_types.iter().filter_map(|t| {
+                        if t.is_ts_type() {
+                            Some(t.to_ts_type())
+                        } else {
+                            None
+                        }
+                    }));
+
+                RSType::Vec(Box::new(make_union_or_option_type(&variants)))
+            }
+            TSType::TSTypeLiteral(value) => match value.members.as_slice() {
+                // e.g. { [key: string]: number }
+                [TSSignature::TSIndexSignature(signature)] if signature.parameters.len() == 1 => {
+                    RSType::HashMap(
+                        Box::new(self.make_rs_type(
+                            &signature.parameters[0].type_annotation.type_annotation,
+                        )),
+                        Box::new(self.make_rs_type(&signature.type_annotation.type_annotation)),
+                    )
                 }
-                TSLiteral::BigIntLiteral(bigint) => {
-                    RSEnumVariant::NumericLiteral(bigint.raw.clone().into_string())
+                members
+                    if members
+                        .iter()
+                        .any(|member| matches!(member, TSSignature::TSIndexSignature(_))) =>
+                {
+                    // e.g. { [key: string]: string; length: number }
+                    self.substituted_type(value, value.span, RSType::JSONValue)
                 }
-                TSLiteral::RegExpLiteral(value) => unimplemented_variant(value, value.span, source),
-                TSLiteral::StringLiteral(string) => {
-                    RSEnumVariant::StringLiteral(string.value.clone().into_string())
+                // e.g. { readonly red: "#f00" } for `const colors = { red: "#f00" } as const`
+                members => RSType::Struct(self.make_struct_type("{ .. }", members)),
+            },
+            TSType::TSTypeOperatorType(operator) => match operator.operator {
+                TSTypeOperatorOperator::Keyof => RSType::KeyOf(
+                    Box::new(self.make_rs_type(&operator.type_annotation)),
+                    self.module_span(operator.span),
+                ),
+                // e.g. readonly string[]
+                TSTypeOperatorOperator::Readonly => self.make_rs_type(&operator.type_annotation),
+                TSTypeOperatorOperator::Unique => self.unimplemented_type(operator, operator.span),
+            },
+            TSType::TSTypePredicate(value) => self.unimplemented_type(value, value.span),
+            TSType::TSTypeQuery(query) => self.make_type_query(query),
+            TSType::TSTypeReference(reference) => {
+                trace!("TSType::TSTypeReference {:#?}", reference);
+                if let Some(params) = &reference.type_parameters {
+                    let type_name = reference.type_name.to_string();
+                    let arguments = self.make_rs_types(params.params.iter());
+                    return make_generic_type(
+                        type_name,
+                        arguments,
+                        self.module_span(reference.span),
+                    );
                 }
-                TSLiteral::TemplateLiteral(value) => {
-                    unimplemented_variant(value, value.span, source)
+                // The parser reads the literal type `true` as a type reference.
+                if reference.type_name.to_string() == "true" {
+                    return RSType::EnumVariant(RSEnumVariant::BooleanLiteral(true));
                 }
-                TSLiteral::UnaryExpression(value) => {
-                    unimplemented_variant(value, value.span, source)
+                RSType::Reference(RSReference::Unresolved {
+                    name: reference.type_name.to_string(),
+                    module_specifier: None,
+                })
+            }
+            TSType::TSUnionType(union) => {
+                make_union_or_option_type(&self.make_rs_types(union.types.iter()))
+            }
+            TSType::TSParenthesizedType(value) => self.make_rs_type(&value.type_annotation),
+            // `?string` is `Option<String>`, and `!string` is `string` without `null`.
+            TSType::JSDocNullableType(value) => match self.make_rs_type(&value.type_annotation) {
+                RSType::Option(inner) => RSType::Option(inner),
+                rs_type => RSType::Option(Box::new(rs_type)),
+            },
+            TSType::JSDocNonNullableType(value) => {
+                match self.make_rs_type(&value.type_annotation) {
+                    RSType::Enum(RSEnum { variants, .. }) => RSType::Enum(RSEnum {
+                        option: false,
+                        variants,
+                    }),
+                    RSType::Option(inner) => *inner,
+                    rs_type => rs_type,
                 }
-            };
-            RSType::EnumVariant(variant)
+            }
+            TSType::JSDocUnknownType(_) => RSType::JSONValue,
+        };
+
+        rs_type
+    }
+
+    fn make_rs_types<'a>(&mut self, types: impl Iterator<Item = &'a TSType<'a>>) -> Vec<RSType> {
+        types.map(|t| self.make_rs_type(t)).collect()
+    }
+
+    /// e.g. `import("./foo").Bar`, as emitted by tsc in generated declaration files,
+    /// and `typeof import("./foo").bar`.
+    fn make_import_type(&mut self, import_type: &TSImportType) -> RSType {
+        let arguments = import_type
+            .type_parameters
+            .as_ref()
+            .map(|params| self.make_rs_types(params.params.iter()));
+        match (self.import_type_reference(import_type), arguments) {
+            (Some(reference), Some(arguments)) if !import_type.is_type_of => {
+                RSType::Instantiation(reference, arguments, self.module_span(import_type.span))
+            }
+            (Some(reference), None) if import_type.is_type_of => {
+                RSType::TypeQuery(reference, self.module_span(import_type.span))
+            }
+            (Some(reference), None) => RSType::Reference(reference),
+            _ => self.unimplemented_type(import_type, import_type.span),
         }
-        TSType::TSMappedType(_) => RSType::JSONValue,
-        TSType::TSNamedTupleMember(named_tuple_member) => {
-            // TODO: Make union type! Needs testing!
-            let element_ts_type = named_tuple_member.element_type.to_ts_type();
-            let element_type = make_rs_type(element_ts_type, source);
-            RSType::EnumVariant(RSEnumVariant::RSType(Box::new(element_type)))
+    }
+
+    /// Resolves the module of `import("./foo").Bar`, and returns a reference to `Bar`.
+    fn import_type_reference(&mut self, import_type: &TSImportType) -> Option<RSReference> {
+        let qualifier = import_type.qualifier.as_ref()?;
+        let TSType::TSLiteralType(literal) = &import_type.parameter else {
+            return None;
+        };
+        let TSLiteral::StringLiteral(specifier) = &literal.literal else {
+            return None;
+        };
+
+        let name = qualifier.to_string();
+        let reference = match self.resolve_module(&specifier.value, specifier.span) {
+            Some(module_path) => RSReference::Imported { name, module_path },
+            None => RSReference::Unresolved {
+                name,
+                module_specifier: Some(specifier.value.to_string()),
+            },
+        };
+        Some(reference)
+    }
+
+    /// e.g. `typeof config`, replaced with the type of the value when linking.
+    fn make_type_query(&mut self, query: &TSTypeQuery) -> RSType {
+        if query.type_parameters.is_some() {
+            return self.unimplemented_type(query, query.span);
         }
-        TSType::TSQualifiedName(value) => unimplemented_type(value, value.span, source),
-        TSType::TSTemplateLiteralType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSThisType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSTupleType(tuple) => {
-            let variants: Vec<RSType> = make_rs_types(
-                tuple.element_types.iter().filter_map(|t| {
-                    if t.is_ts_type() {
-                        Some(t.to_ts_type())
-                    } else {
-                        None
-                    }
+        let reference = match &query.expr_name {
+            TSTypeQueryExprName::TSImportType(import_type) => {
+                self.import_type_reference(import_type)
+            }
+            expr_name => expr_name
+                .as_ts_type_name()
+                .map(|name| RSReference::Unresolved {
+                    name: name.to_string(),
+                    module_specifier: None,
                 }),
-                source,
+        };
+        match reference {
+            Some(reference) => RSType::TypeQuery(reference, self.module_span(query.span)),
+            None => self.unimplemented_type(query, query.span),
+        }
+    }
+
+    /// Converts a mapped type like `{ [K in keyof T]?: T[K] }`, which is evaluated when linking.
+    /// Key remapping with `as` is not supported.
+    fn make_mapped_type(&mut self, mapped: &TSMappedType) -> RSType {
+        let (Some(constraint), None) = (&mapped.type_parameter.constraint, &mapped.name_type)
+        else {
+            return self.substituted_type(mapped, mapped.span, RSType::JSONValue);
+        };
+        let value = match &mapped.type_annotation {
+            Some(type_annotation) => self.make_rs_type(type_annotation),
+            None => RSType::JSONValue,
+        };
+        // `readonly` has no Rust equivalent.
+        let optional = match mapped.optional {
+            TSMappedTypeModifierOperator::True | TSMappedTypeModifierOperator::Plus => Some(true),
+            TSMappedTypeModifierOperator::Minus => Some(false),
+            TSMappedTypeModifierOperator::None => None,
+        };
+        RSType::Mapped(RSMappedType {
+            key_name: mapped.type_parameter.name.name.to_string(),
+            keys: Box::new(self.make_rs_type(constraint)),
+            value: Box::new(value),
+            optional,
+            span: self.module_span(mapped.span),
+        })
+    }
+
+    /// Converts the property signatures of an interface or type literal to the fields of a struct.
+    /// Other members are dropped with a warning.
+    pub(super) fn make_struct_type(&mut self, owner: &str, members: &[TSSignature]) -> RSStruct {
+        let mut fields: HashMap<String, RSType> = HashMap::new();
+        for member in members {
+            let property = match member {
+                TSSignature::TSPropertySignature(property) => property,
+                TSSignature::TSMethodSignature(method) => {
+                    let what = format!(
+                        "method `{}.{}`",
+                        owner,
+                        method.key.name().unwrap_or_default()
+                    );
+                    self.warn(method, method.span, Fallback::Dropped(what));
+                    continue;
+                }
+                TSSignature::TSIndexSignature(signature) => {
+                    let what = format!("index signature of `{}`", owner);
+                    self.warn(signature, signature.span, Fallback::Dropped(what));
+                    continue;
+                }
+                TSSignature::TSCallSignatureDeclaration(signature) => {
+                    let what = format!("call signature of `{}`", owner);
+                    self.warn(signature, signature.span, Fallback::Dropped(what));
+                    continue;
+                }
+                TSSignature::TSConstructSignatureDeclaration(signature) => {
+                    let what = format!("construct signature of `{}`", owner);
+                    self.warn(signature, signature.span, Fallback::Dropped(what));
+                    continue;
+                }
+            };
+            let field_name = match property.computed {
+                true => {
+                    let key_span = self.module_span(property.key.span());
+                    format!("[{}]", key_span.source_text(&self.source_text))
+                }
+                false => match property.key.name() {
+                    Some(field_name) => field_name.to_string(),
+                    None => continue,
+                },
+            };
+            let ts_type = match &property.type_annotation {
+                Some(type_annotation) => &type_annotation.type_annotation,
+                None => {
+                    let what = format!("untyped field `{}.{}`", owner, field_name);
+                    self.warn(property, property.span, Fallback::Dropped(what));
+                    continue;
+                }
+            };
+            let unique_symbol = matches!(
+                ts_type,
+                TSType::TSTypeOperatorType(operator)
+                    if matches!(operator.operator, TSTypeOperatorOperator::Unique)
             );
+            let warnings_before = self.warnings.len();
+            let rs_type = match unique_symbol {
+                true => RSType::JSONValue,
+                false => self.make_rs_type(ts_type),
+            };
+            let rs_type = make_field_type(rs_type, property.optional);
+            let what = format!("field `{}.{}`", owner, field_name);
+            if self.drop_unimplemented_field(&rs_type, warnings_before, &what) {
+                continue;
+            }
 
-            RSType::Vec(Box::new(make_union_or_option_type(&variants)))
-        }
-        TSType::TSTypeLiteral(_) => {
-            // unimplemented!("TSTypeLiteral: {:#?}", literal)
-            // e.g. { [key: string]: string }
-            RSType::JSONValue
+            // Computed and `unique symbol` fields are kept to brand an intersection.
+            let rs_type = match property.computed || unique_symbol {
+                true => RSType::Brand(Box::new(rs_type), self.module_span(property.span)),
+                false => rs_type,
+            };
+            fields.insert(field_name, rs_type);
         }
-        TSType::TSTypeOperatorType(value) => unimplemented_type(value, value.span, source),
-        TSType::TSTypePredicate(value) => unimplemented_type(value, value.span, source),
-        TSType::TSTypeQuery(value) => unimplemented_type(value, value.span, source),
-        TSType::TSTypeReference(reference) => {
-            println!("TSType::TSTypeReference {:#?}", reference);
-            if let Some(params) = &reference.type_parameters {
-                return make_union_or_option_type(&make_rs_types(params.params.iter(), source));
-            }
-            RSType::Reference(RSReference::Unresolved {
-                name: reference.type_name.to_string(),
-                module_specifier: None,
-            })
+
+        RSStruct { fields }
+    }
+
+    /// With `ignore_unimplemented`, a field whose type is unimplemented is dropped, not kept as
+    /// unimplemented, and the warnings found since `warnings_before` say so.
+    /// Returns true if the field is dropped.
+    pub(super) fn drop_unimplemented_field(
+        &mut self,
+        rs_type: &RSType,
+        warnings_before: usize,
+        what: &str,
+    ) -> bool {
+        if !self.options.ignore_unimplemented || !matches!(rs_type, RSType::Unimplemented(_, _, _))
+        {
+            return false;
         }
-        TSType::TSUnionType(union) => {
-            make_union_or_option_type(&make_rs_types(union.types.iter(), source))
+        for warning in &mut self.warnings[warnings_before..] {
+            warning.fallback = Fallback::Dropped(what.to_string());
         }
-        TSType::TSParenthesizedType(value) => unimplemented_type(value, value.span, source),
-        TSType::JSDocNullableType(value) => unimplemented_type(value, value.span, source),
-        TSType::JSDocNonNullableType(value) => unimplemented_type(value, value.span, source),
-        TSType::JSDocUnknownType(value) => unimplemented_type(value, value.span, source),
-    };
+        true
+    }
 
-    rs_type
+    /// Infers the type of a `const` from its literal initializer, like `const kind = "a"`
+    /// or `const colors = { red: "#f00" } as const`. Returns None for other expressions.
+    pub(super) fn make_value_type(&mut self, expression: &Expression) -> Option<RSType> {
+        let rs_type = match expression {
+            Expression::StringLiteral(string) => RSType::EnumVariant(RSEnumVariant::StringLiteral(
+                string.value.clone().into_string(),
+            )),
+            Expression::NumericLiteral(numeric) => {
+                RSType::EnumVariant(RSEnumVariant::NumericLiteral(numeric.raw.into()))
+            }
+            Expression::BooleanLiteral(boolean) => {
+                RSType::EnumVariant(RSEnumVariant::BooleanLiteral(boolean.value))
+            }
+            Expression::NullLiteral(_) => RSType::NullOrUndefined,
+            Expression::TSAsExpression(expression) => match &expression.type_annotation {
+                TSType::TSTypeReference(reference)
+                    if reference.type_name.to_string() == "const" =>
+                {
+                    return self.make_value_type(&expression.expression)
+                }
+                type_annotation => self.make_rs_type(type_annotation),
+            },
+            Expression::ParenthesizedExpression(expression) => {
+                return self.make_value_type(&expression.expression)
+            }
+            Expression::ArrayExpression(array) => {
+                let element_types = array
+                    .elements
+                    .iter()
+                    .map(|element| {
+                        element
+                            .as_expression()
+                            .and_then(|element| self.make_value_type(element))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                RSType::Vec(Box::new(make_union_or_option_type(&element_types)))
+            }
+            Expression::ObjectExpression(object) => {
+                let mut fields: HashMap<String, RSType> = HashMap::new();
+                for property in &object.properties {
+                    let ObjectPropertyKind::ObjectProperty(property) = property else {
+                        return None;
+                    };
+                    let name = match &property.key {
+                        PropertyKey::StaticIdentifier(id) => id.name.to_string(),
+                        PropertyKey::StringLiteral(string) => string.value.to_string(),
+                        _ => return None,
+                    };
+                    fields.insert(name, self.make_value_type(&property.value)?);
+                }
+                RSType::Struct(RSStruct { fields })
+            }
+            _ => return None,
+        };
+        Some(rs_type)
+    }
+
+    /// Records a warning for a construct of type `T` at `span`.
+    pub(super) fn warn<T>(&mut self, value: &T, span: Span, fallback: Fallback) {
+        self.warn_construct(extract_type_name(value), span, fallback);
+    }
+
+    /// Records a lossy conversion of a construct that is not an AST node, e.g. a JSDoc type.
+    pub(super) fn warn_construct(&mut self, construct: String, span: Span, fallback: Fallback) {
+        self.warnings.push(ConversionWarning {
+            module_path: self.path.clone(),
+            span: self.module_span(span),
+            construct,
+            fallback,
+        });
+    }
+
+    fn substituted_type<T>(&mut self, value: &T, span: Span, rs_type: RSType) -> RSType {
+        self.warn(value, span, Fallback::Substituted(rs_type.name()));
+        rs_type
+    }
+
+    fn unimplemented_variant<T>(&mut self, value: &T, span: Span) -> RSEnumVariant {
+        self.warn(value, span, Fallback::Unimplemented);
+        let span = self.module_span(span);
+        RSEnumVariant::Unimplemented(
+            extract_type_name(value),
+            span.source_text(&self.source_text).to_string(),
+            span,
+        )
+    }
+
+    fn unimplemented_type<T>(&mut self, value: &T, span: Span) -> RSType {
+        self.warn(value, span, Fallback::Unimplemented);
+        let span = self.module_span(span);
+        RSType::Unimplemented(
+            extract_type_name(value),
+            span.source_text(&self.source_text).to_string(),
+            span,
+        )
+    }
 }
 
-fn make_rs_types<'a>(types: impl Iterator<Item = &'a TSType<'a>>, source: &str) -> Vec<RSType> {
-    types.map(|t| make_rs_type(t, source)).collect()
+/// Converts a generic type with type arguments, e.g. `Array<string>` or `Page<User>`.
+/// The array, promise and utility types of the standard library are built in, with the
+/// mapped types of `lib.d.ts`, e.g. `Partial<T>` as `{ [P in keyof T]?: T[P] }`.
+fn make_generic_type(type_name: String, mut arguments: Vec<RSType>, span: Span) -> RSType {
+    // `Exclude<T, U>` is `T extends U ? never : T`, distributed over `T`.
+    let distributive = |check_type: RSType, extends_type: RSType, excluded: bool| {
+        let parameter = RSType::Reference(RSReference::Unresolved {
+            name: "T".to_string(),
+            module_specifier: None,
+        });
+        let (true_type, false_type) = match excluded {
+            true => (RSType::Never, parameter),
+            false => (parameter, RSType::Never),
+        };
+        RSType::Conditional(RSConditionalType {
+            check_type: Box::new(check_type),
+            extends_type: Box::new(extends_type),
+            true_type: Box::new(true_type),
+            false_type: Box::new(false_type),
+            distributive_parameter: Some("T".to_string()),
+            span,
+        })
+    };
+
+    // `{ [P in keys]: T[P] }`, with the properties of `T` made optional or required.
+    let homomorphic = |object: RSType, keys: RSType, optional: Option<bool>| {
+        let key = RSType::Reference(RSReference::Unresolved {
+            name: "P".to_string(),
+            module_specifier: None,
+        });
+        RSType::Mapped(RSMappedType {
+            key_name: "P".to_string(),
+            keys: Box::new(keys),
+            value: Box::new(RSType::IndexedAccess(Box::new(object), Box::new(key), span)),
+            optional,
+            span,
+        })
+    };
+    let key_of = |object: &RSType| RSType::KeyOf(Box::new(object.clone()), span);
+
+    match (type_name.as_str(), arguments.len()) {
+        // e.g. Array<string>, the same as string[]
+        ("Array" | "ReadonlyArray", 1) => RSType::Vec(Box::new(arguments.remove(0))),
+        ("Promise" | "PromiseLike", 1) => RSType::Promise(Box::new(arguments.remove(0))),
+        ("NonNullable", 1) => distributive(arguments.remove(0), RSType::NullOrUndefined, true),
+        ("Exclude" | "Extract", 2) => {
+            let extends_type = arguments.remove(1);
+            distributive(arguments.remove(0), extends_type, type_name == "Exclude")
+        }
+        ("Partial" | "Required", 1) => {
+            let object = arguments.remove(0);
+            let keys = key_of(&object);
+            homomorphic(object, keys, Some(type_name == "Partial"))
+        }
+        ("Pick", 2) => {
+            let keys = arguments.remove(1);
+            homomorphic(arguments.remove(0), keys, None)
+        }
+        // `Omit<T, K>` is `Pick<T, Exclude<keyof T, K>>`.
+        ("Omit", 2) => {
+            let omitted = arguments.remove(1);
+            let object = arguments.remove(0);
+            let keys = distributive(key_of(&object), omitted, true);
+            homomorphic(object, keys, None)
+        }
+        ("Record", 2) => RSType::Mapped(RSMappedType {
+            key_name: "P".to_string(),
+            keys: Box::new(arguments.remove(0)),
+            value: Box::new(arguments.remove(0)),
+            optional: None,
+            span,
+        }),
+        _ => RSType::Instantiation(
+            RSReference::Unresolved {
+                name: type_name,
+                module_specifier: None,
+            },
+            arguments,
+            span,
+        ),
+    }
 }
 
-fn make_union_or_option_type(types: &[RSType]) -> RSType {
-    // println!("make_union_or_option_type {:#?}", types);
+pub(super) fn make_union_or_option_type(types: &[RSType]) -> RSType {
+    // trace!("make_union_or_option_type {:#?}", types);
     let mut option = false;
     let variants: Vec<RSType> = types
         .iter()
@@ -131,32 +623,37 @@ fn make_union_or_option_type(types: &[RSType]) -> RSType {
                 option = true;
                 None
             }
-            RSType::Unit => None,
+            RSType::Unit | RSType::Never => None,
             _ => Some(t.clone()),
         })
         .collect();
     RSType::Enum(RSEnum { option, variants })
 }
 
-fn unimplemented_variant<T: Serialize>(value: &T, span: Span, source: &str) -> RSEnumVariant {
-    RSEnumVariant::Unimplemented(
-        extract_type_name(value),
-        span.source_text(source).to_string(),
-    )
+/// Returns the type of an optional field, or of a field with a nullable union type, as an `Option`.
+/// A promise is converted to the type of its value.
+pub(super) fn make_field_type(rs_type: RSType, optional: bool) -> RSType {
+    match rs_type {
+        RSType::Promise(inner) => make_field_type(*inner, optional),
+        RSType::Option(inner) => RSType::Option(inner),
+        RSType::Enum(rs_enum) => match optional || rs_enum.option {
+            true => RSType::Option(Box::new(RSType::Enum(rs_enum))),
+            false => RSType::Enum(rs_enum),
+        },
+        _ => match optional {
+            true => RSType::Option(Box::new(rs_type)),
+            false => rs_type,
+        },
+    }
 }
 
-fn extract_type_name<T: Serialize>(value: &T) -> String {
+fn extract_type_name<T>(value: &T) -> String {
     type_name_of_val(value)
         .split("::")
         .last()
         .unwrap()
-        .replace('>', "")
+        .split(['<', '>'])
+        .next()
+        .unwrap()
         .to_string()
 }
-
-fn unimplemented_type<T: Serialize>(value: &T, span: Span, source: &str) -> RSType {
-    RSType::Unimplemented(
-        extract_type_name(value),
-        span.source_text(source).to_string(),
-    )
-}
@@ -19,22 +19,32 @@ use oxc_ast::{
     ast::{self, Program},
     Visit,
 };
-use oxc_diagnostics::OxcDiagnostic;
+use oxc_diagnostics::{NamedSource, OxcDiagnostic, Severity};
+use oxc_isolated_declarations::{IsolatedDeclarations, IsolatedDeclarationsOptions};
 use oxc_parser::{ParseOptions, Parser, ParserReturn};
-use oxc_span::SourceType;
+use oxc_span::{SourceType, Span};
+use tracing::{info, trace};
 
-mod errors;
+pub(crate) mod errors;
+#[cfg(test)]
+mod fixtures;
+mod jsdoc;
+mod json;
 mod make_rs_type;
+mod module;
 pub(crate) mod options;
 mod reference_resolver;
 mod visitor;
 mod visitor_impl;
+pub(crate) mod warnings;
 
-use oxc_resolver::{ResolveOptions, Resolver};
+use oxc_resolver::{ResolveOptions, Resolver, TsconfigOptions, TsconfigReferences};
 
-use make_rs_type::*;
+use json::infer_json_type;
+use module::Module;
 use reference_resolver::ReferenceResolver;
 use visitor::TypeScriptToRustVisitor;
+use warnings::ConversionWarning;
 
 use crate::rs_types::{RSType, RSTypeMap};
 
@@ -45,7 +55,15 @@ pub(crate) struct TypeScriptToRustBuilder {
     /// The options used to configure the TypeScript to Rust conversion.
     options: TypeScriptOptions,
     /// The TypeScript modules and their types.
-    modules: HashMap<PathBuf, RSTypeMap>,
+    modules: HashMap<PathBuf, Module>,
+    /// Recoverable problems (e.g. unresolvable imports) found in all visited modules.
+    diagnostics: Vec<oxc_diagnostics::Error>,
+    /// Parser errors found in all visited modules.
+    errors: Vec<oxc_diagnostics::Error>,
+    /// Lossy or unimplemented conversions found in all visited modules.
+    warnings: Vec<ConversionWarning>,
+    /// The sources of the visited modules, to report problems found when linking them.
+    sources: HashMap<PathBuf, Arc<NamedSource<String>>>,
 }
 
 impl TypeScriptToRustBuilder {
@@ -56,8 +74,157 @@ impl TypeScriptToRustBuilder {
         }
     }
 
+    /// Returns the recoverable problems found so far, with their module sources attached.
+    pub fn diagnostics(&self) -> &[oxc_diagnostics::Error] {
+        &self.diagnostics
+    }
+
+    /// Returns the lossy or unimplemented conversions found so far.
+    /// These are also reported in `diagnostics`, or as errors in strict mode.
+    pub fn warnings(&self) -> &[ConversionWarning] {
+        &self.warnings
+    }
+
+    /// Reports a recoverable problem in a visited module.
+    fn report(&mut self, module_path: &Path, diagnostic: OxcDiagnostic) {
+        let diagnostic = match self.sources.get(module_path) {
+            Some(source) => diagnostic.with_source_code(Arc::clone(source)),
+            None => diagnostic.into(),
+        };
+        self.diagnostics.push(diagnostic);
+    }
+
     /// Visits a TypeScript module and its dependencies.
+    /// Returns a `DiagnosticsError` with the parser errors of every visited module, if any,
+    /// and with every lossy conversion in strict mode.
     pub fn visit_module<R: AsRef<Path>>(&mut self, path: R) -> Result<(), Box<dyn Error>> {
+        self.visit_modules([path])
+    }
+
+    /// Visits several entry modules and their dependencies, and links them together,
+    /// e.g. so that script declaration files can contribute to the global scope.
+    /// The `tsconfig.json` is discovered from the first entry module.
+    pub fn visit_modules<R: AsRef<Path>>(
+        &mut self,
+        paths: impl IntoIterator<Item = R>,
+    ) -> Result<(), Box<dyn Error>> {
+        for (index, path) in paths.into_iter().enumerate() {
+            let path = path.as_ref().canonicalize()?;
+            if index == 0 {
+                self.configure_tsconfig(&path)?;
+            }
+            self.visit_module_recursive(path)?;
+        }
+
+        // After all modules have been visited, resolve type references
+        self.resolve_star_exports();
+        self.resolve_references();
+        self.merge_globals();
+        trace!("RESOLVED: {:#?}", self.modules);
+
+        if !self.errors.is_empty() {
+            return Err(Box::new(DiagnosticsError {
+                diagnostics: std::mem::take(&mut self.errors),
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Infers the type of a JSON module, which is the type of its default export.
+    fn visit_json_module(
+        &mut self,
+        path: PathBuf,
+        source_text: &str,
+        named_source: &Arc<NamedSource<String>>,
+    ) {
+        match serde_json::from_str::<serde_json::Value>(source_text) {
+            Ok(value) => {
+                let mut module = Module::default();
+                module
+                    .values
+                    .insert("default".to_string(), infer_json_type(&value));
+                self.modules.insert(path, module);
+            }
+            Err(error) => {
+                let offset = source_text
+                    .split_inclusive('\n')
+                    .take(error.line().saturating_sub(1))
+                    .map(str::len)
+                    .sum::<usize>()
+                    + error.column().saturating_sub(1);
+                let span = Span::new(offset as u32, offset as u32);
+                let diagnostic = OxcDiagnostic::error(format!("Invalid JSON module: {}", error))
+                    .with_label(span);
+                self.errors
+                    .push(diagnostic.with_source_code(Arc::clone(named_source)));
+            }
+        }
+    }
+
+    /// Reports lossy conversions of a visited module, as errors in strict mode.
+    fn report_lossy(
+        &mut self,
+        named_source: &Arc<NamedSource<String>>,
+        diagnostics: impl Iterator<Item = OxcDiagnostic>,
+    ) {
+        let diagnostics = diagnostics.map(|diagnostic| {
+            let diagnostic = match self.options.strict {
+                true => diagnostic
+                    .with_severity(Severity::Error)
+                    .with_help("Lossy conversions are not allowed in strict mode."),
+                false => diagnostic,
+            };
+            diagnostic.with_source_code(Arc::clone(named_source))
+        });
+        match self.options.strict {
+            true => self.errors.extend(diagnostics),
+            false => self.diagnostics.extend(diagnostics),
+        }
+    }
+
+    /// Reports lossy conversions found when linking, as errors in strict mode.
+    fn report_warnings(&mut self, warnings: Vec<ConversionWarning>) {
+        for warning in warnings {
+            if let Some(named_source) = self.sources.get(&warning.module_path).cloned() {
+                self.report_lossy(&named_source, std::iter::once(warning.to_diagnostic()));
+            }
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Configures the resolver with the `tsconfig.json` given in the options,
+    /// or with the nearest one found from the directory of the entrypoint.
+    fn configure_tsconfig(&mut self, entrypoint: &Path) -> Result<(), Box<dyn Error>> {
+        if self.options.resolve_options.tsconfig.is_some() {
+            return Ok(());
+        }
+
+        let config_file = match &self.options.tsconfig {
+            Some(config_file) => Some(
+                config_file
+                    .canonicalize()
+                    .map_err(|error| format!("{}: {}", config_file.display(), error))?,
+            ),
+            None => entrypoint
+                .ancestors()
+                .skip(1)
+                .map(|directory| directory.join("tsconfig.json"))
+                .find(|config_file| config_file.is_file()),
+        };
+
+        if let Some(config_file) = config_file {
+            info!("tsconfig: {:?}", config_file);
+            self.options.resolve_options.tsconfig = Some(TsconfigOptions {
+                config_file,
+                references: TsconfigReferences::Auto,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn visit_module_recursive<R: AsRef<Path>>(&mut self, path: R) -> Result<(), Box<dyn Error>> {
         let path = path.as_ref().canonicalize()?;
 
         // Skip module if already processed.
@@ -65,18 +232,50 @@ impl TypeScriptToRustBuilder {
             return Ok(());
         }
 
-        self.modules.insert(path.clone(), RSTypeMap::new());
+        self.modules.insert(path.clone(), Module::default());
 
-        println!("visit_module: {:?}", path);
+        info!("visit_module: {:?}", path);
 
         // Read and parse the module
         let source_text = fs::read_to_string(&path)?;
+        let named_source = Arc::new(NamedSource::new(
+            path.to_string_lossy(),
+            source_text.clone(),
+        ));
+        self.sources.insert(path.clone(), Arc::clone(&named_source));
+
+        // `import config from "./config.json"` (`resolveJsonModule`)
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            self.visit_json_module(path, &source_text, &named_source);
+            return Ok(());
+        }
+
         let source_type = SourceType::from_path(&path)?;
+        // `.cts` files are CommonJS, but TypeScript still writes their imports and exports
+        // as ES module declarations.
+        let source_type = match source_type.is_typescript() {
+            true => source_type.with_module(true),
+            false => source_type,
+        };
         let allocator = Allocator::default();
         let parser = Parser::new(&allocator, &source_text, source_type)
             .with_options(self.options.parse_options);
         let ret = parser.parse();
 
+        self.errors.extend(
+            ret.errors
+                .into_iter()
+                .map(|error| error.with_source_code(Arc::clone(&named_source))),
+        );
+
+        // The program is empty if the parser panicked, so there is nothing to visit.
+        if ret.panicked {
+            return Ok(());
+        }
+
         // Create and use the visitor
         let resolver = Resolver::new(self.options.resolve_options.clone());
         let mut visitor = TypeScriptToRustVisitor::new(
@@ -86,23 +285,375 @@ impl TypeScriptToRustBuilder {
             self.options.clone(),
         );
 
-        visitor.visit_program(&ret.program);
+        // Derive the declarations of TypeScript sources, like `tsc --isolatedDeclarations`.
+        let declarations = match source_type.is_typescript_definition() {
+            false if source_type.is_typescript() => {
+                let options = IsolatedDeclarationsOptions {
+                    strip_internal: false,
+                };
+                let mut declarations =
+                    IsolatedDeclarations::new(&allocator, &source_text, &ret.trivias, options)
+                        .build(&ret.program);
+                // Keep the source type so that scripts are not mistaken for global declarations.
+                declarations.program.source_type = source_type;
+                Some(declarations)
+            }
+            _ => None,
+        };
+
+        visitor.visit_reference_directives(&ret.program, &ret.trivias);
+        match &declarations {
+            Some(declarations) => visitor.visit_program(&declarations.program),
+            None => visitor.visit_program(&ret.program),
+        }
+        // JavaScript modules declare their types in JSDoc comments.
+        if source_type.is_javascript() {
+            visitor.visit_jsdoc_typedefs(&ret.trivias);
+        }
 
         // Store the result
-        let mut type_map = self.modules.get_mut(&path).unwrap();
-        *type_map = visitor.types.clone();
+        self.modules.insert(path.clone(), visitor.module.clone());
+
+        self.diagnostics.extend(
+            visitor
+                .diagnostics
+                .drain(..)
+                .map(|diagnostic| diagnostic.with_source_code(Arc::clone(&named_source))),
+        );
+
+        // Declarations that cannot be derived without a type checker are left out.
+        let declaration_diagnostics = declarations
+            .into_iter()
+            .flat_map(|declarations| declarations.errors)
+            .map(|diagnostic| {
+                diagnostic
+                    .with_severity(Severity::Warning)
+                    .with_help("Add an explicit type annotation to convert this declaration.")
+            });
+        let conversion_diagnostics = visitor
+            .warnings
+            .iter()
+            .map(ConversionWarning::to_diagnostic);
+        self.report_lossy(
+            &named_source,
+            declaration_diagnostics.chain(conversion_diagnostics),
+        );
+        self.warnings.append(&mut visitor.warnings);
 
         // Resolve dependencies
-        for mapping in visitor.type_mappings.values() {
-            if let Some(original_module_path) = &mapping.original_module {
-                // Recursively visit the original module
-                self.visit_module(original_module_path)?;
+        for (dependency, span) in &visitor.dependencies {
+            // Recursively visit the original module. A module that cannot be read or parsed,
+            // e.g. `import styles from "./styles.css"`, is skipped.
+            if let Err(error) = self.visit_module_recursive(dependency) {
+                self.report(
+                    &path,
+                    OxcDiagnostic::warn(format!(
+                        "Failed to load module {:?}: {}",
+                        dependency, error
+                    ))
+                    .with_label(span.label("imported here"))
+                    .with_help("Types from this module will be left unresolved."),
+                );
             }
         }
 
-        // After all modules have been visited, resolve type references
-        // self.resolve_type_references(&path)?;
-
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fixtures::*;
+
+    use super::*;
+    use crate::rs_types::*;
+
+    #[test]
+    fn unresolvable_modules_are_reported_and_skipped() {
+        let fixture = Fixture::new(
+            "unresolvable_modules",
+            &[
+                (
+                    "index.ts",
+                    r#"
+                    import { Missing } from "not-installed";
+                    import type { Classes } from "./styles.css";
+                    export interface Button { kind: Missing; classes: Classes; label: string }
+                    "#,
+                ),
+                ("styles.css", ".button { color: red; }"),
+            ],
+        );
+        let builder = fixture.convert("index.ts");
+        let types = types(&builder, &fixture.path("index.ts"));
+        let RSType::Struct(button) = &types["Button"] else {
+            panic!("`Button` is a struct");
+        };
+        assert_eq!(button.fields["label"], STRING);
+        assert_eq!(
+            button.fields["kind"],
+            RSType::Reference(RSReference::Unresolved {
+                name: "Missing".to_string(),
+                module_specifier: Some("not-installed".to_string()),
+            })
+        );
+        assert!(matches!(
+            &button.fields["classes"],
+            RSType::Reference(RSReference::Unresolved { name, .. }) if name == "Classes"
+        ));
+
+        let diagnostics = diagnostics(&builder);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].starts_with("Failed to resolve module specifier `not-installed`"));
+        assert!(diagnostics[1].starts_with("Failed to load module"));
+        assert!(diagnostics[1].contains("styles.css"));
+        assert!(warnings(&builder).is_empty());
+    }
+
+    #[test]
+    fn parser_errors_of_every_module_fail_the_conversion() {
+        let fixture = Fixture::new(
+            "parser_errors",
+            &[
+                (
+                    "index.ts",
+                    "import type { B } from './broken';\nexport interface A { b: B }\n",
+                ),
+                ("broken.ts", "export interface B { b: string\n"),
+            ],
+        );
+        let (_, result) = fixture.visit(&["index.ts"], TypeScriptOptions::default());
+        let error = result.expect_err("the conversion fails");
+        assert!(error.contains("Expected `}` but found `EOF`"));
+        assert!(error.contains("broken.ts:2:1"));
+        assert!(error.contains("export interface B { b: string"));
+    }
+
+    #[test]
+    fn lossy_conversions_are_recorded_as_warnings() {
+        let fixture = Fixture::new(
+            "lossy_conversions",
+            &[(
+                "index.ts",
+                "export interface A { id: string; data: any; update(): void }\n",
+            )],
+        );
+        let builder = fixture.convert("index.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["A"],
+            object(vec![("id", STRING), ("data", RSType::JSONValue)])
+        );
+        assert_eq!(
+            warnings(&builder),
+            [
+                "`TSAnyKeyword` converted to `serde_json::Value`",
+                "`TSMethodSignature` dropped: method `A.update`",
+            ]
+        );
+        let source = fs::read_to_string(fixture.path("index.ts")).unwrap();
+        let warning = &builder.warnings()[1];
+        assert_eq!(warning.module_path, fixture.path("index.ts"));
+        assert_eq!(warning.span.source_text(&source), "update(): void");
+        // The warnings are also reported as diagnostics.
+        assert_eq!(diagnostics(&builder), warnings(&builder));
+    }
+
+    #[test]
+    fn strict_mode_fails_on_every_lossy_conversion() {
+        let fixture = Fixture::new(
+            "strict_mode",
+            &[(
+                "index.ts",
+                "export interface A { data: object; [key: string]: unknown; update(): void }\n",
+            )],
+        );
+        let options = TypeScriptOptions {
+            strict: true,
+            ..TypeScriptOptions::default()
+        };
+        let (builder, result) = fixture.visit(&["index.ts"], options);
+        let error = result.expect_err("the conversion fails");
+        for warning in warnings(&builder) {
+            assert!(error.contains(&warning), "{} is reported", warning);
+        }
+        assert_eq!(builder.warnings().len(), 3);
+        assert_eq!(
+            error
+                .matches("Lossy conversions are not allowed in strict mode.")
+                .count(),
+            3
+        );
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn tsconfig_paths_and_base_url_resolve_modules() {
+        let fixture = Fixture::new(
+            "tsconfig_paths",
+            &[
+                (
+                    "tsconfig.json",
+                    r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@app/*": ["packages/*/src"] } } }"#,
+                ),
+                (
+                    "packages/models/src/index.ts",
+                    "export interface User { id: string }\n",
+                ),
+                ("shared/ids.ts", "export type Id = string;\n"),
+                (
+                    "app/index.ts",
+                    r#"
+                    import type { User } from "@app/models";
+                    import type { Id } from "shared/ids";
+                    export interface Session { user: User; id: Id }
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("app/index.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("app/index.ts"))["Session"],
+            object(vec![
+                (
+                    "user",
+                    resolved("User", &fixture.path("packages/models/src/index.ts"))
+                ),
+                ("id", resolved("Id", &fixture.path("shared/ids.ts"))),
+            ])
+        );
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn types_packages_and_export_conditions_resolve_modules() {
+        let fixture = Fixture::new(
+            "types_packages",
+            &[
+                (
+                    "node_modules/untyped/package.json",
+                    r#"{ "name": "untyped", "main": "index.js" }"#,
+                ),
+                ("node_modules/untyped/index.js", "module.exports = {};\n"),
+                (
+                    "node_modules/@types/untyped/index.d.ts",
+                    "export interface Options { verbose: boolean }\n",
+                ),
+                (
+                    "node_modules/modern/package.json",
+                    r#"{ "name": "modern", "exports": { ".": { "types": "./dist/types.d.ts", "import": "./dist/index.mjs" } } }"#,
+                ),
+                ("node_modules/modern/dist/index.mjs", "export {};\n"),
+                (
+                    "node_modules/modern/dist/types.d.ts",
+                    "export interface Client { url: string }\n",
+                ),
+                (
+                    "index.ts",
+                    r#"
+                    import type { Options } from "untyped";
+                    import type { Client } from "modern";
+                    export interface Config { options: Options; client: Client }
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("index.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["Config"],
+            object(vec![
+                (
+                    "options",
+                    resolved(
+                        "Options",
+                        &fixture.path("node_modules/@types/untyped/index.d.ts")
+                    )
+                ),
+                (
+                    "client",
+                    resolved(
+                        "Client",
+                        &fixture.path("node_modules/modern/dist/types.d.ts")
+                    )
+                ),
+            ])
+        );
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn declaration_and_source_variants_are_resolved_like_tsc() {
+        let fixture = Fixture::new(
+            "extension_variants",
+            &[
+                ("esm.d.mts", "export interface Esm { a: string }\n"),
+                ("esm.mjs", "export {};\n"),
+                ("cjs.cts", "export interface Cjs { b: string }\n"),
+                ("both.d.ts", "export interface Both { declared: string }\n"),
+                ("both.ts", "export interface Both { source: string }\n"),
+                ("view.tsx", "export interface Props { c: string }\nexport function View(props: Props): unknown { return <div />; }\n"),
+                (
+                    "index.mts",
+                    r#"
+                    import type { Esm } from "./esm.mjs";
+                    import type { Cjs } from "./cjs.cjs";
+                    import type { Both } from "./both.js";
+                    import type { Props } from "./view.js";
+                    export interface All { esm: Esm; cjs: Cjs; both: Both; props: Props }
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("index.mts");
+        // Declaration files are preferred over sources, and `.js` specifiers find the
+        // declarations or sources of every variant.
+        assert_eq!(
+            types(&builder, &fixture.path("index.mts"))["All"],
+            object(vec![
+                ("esm", resolved("Esm", &fixture.path("esm.d.mts"))),
+                ("cjs", resolved("Cjs", &fixture.path("cjs.cts"))),
+                ("both", resolved("Both", &fixture.path("both.d.ts"))),
+                ("props", resolved("Props", &fixture.path("view.tsx"))),
+            ])
+        );
+        assert!(!builder.modules.contains_key(&fixture.path("both.ts")));
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn declarations_are_derived_from_sources() {
+        let source = r#"
+            export interface Options { retries: number }
+            export const defaults: Options = { retries: 3 };
+            export const mode = "fast";
+            export function create() { return defaults; }
+            "#;
+        let fixture = Fixture::new("isolated_declarations", &[("index.ts", source)]);
+        let builder = fixture.convert("index.ts");
+        let module = &builder.modules[&fixture.path("index.ts")];
+        assert_eq!(module.types["Options"], object(vec![("retries", NUMBER)]));
+        assert_eq!(
+            module.values["defaults"],
+            resolved("Options", &fixture.path("index.ts"))
+        );
+        assert_eq!(module.values["mode"], string_literal("fast"));
+        // The return type of `create` cannot be derived without a type checker.
+        assert_eq!(
+            diagnostics(&builder),
+            [
+                "TS9007: Function must have an explicit return type annotation with \
+                --isolatedDeclarations."
+            ]
+        );
+        assert!(warnings(&builder).is_empty());
+
+        let options = TypeScriptOptions {
+            strict: true,
+            ..TypeScriptOptions::default()
+        };
+        let (_, result) = fixture.visit(&["index.ts"], options);
+        assert!(result.expect_err("strict mode fails").contains("TS9007"));
+    }
+}
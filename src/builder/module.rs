@@ -0,0 +1,73 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use oxc_span::Span;
+
+use crate::rs_types::{RSType, RSTypeMap};
+
+use super::visitor::TypeMapping;
+
+/// The path of the pseudo-module holding the global declarations of all modules.
+pub(crate) const GLOBAL_SCOPE: &str = "<global>";
+
+/// The types and import/export edges of a visited TypeScript module.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Module {
+    /// The types declared in this module, keyed by their namespace-qualified name (e.g. `axe.Result`).
+    pub types: RSTypeMap,
+    /// The types this module declares in the global scope, e.g. with `declare global`,
+    /// keyed by their namespace-qualified name.
+    pub globals: RSTypeMap,
+    /// The types of the values declared in this module, for `typeof` queries,
+    /// keyed by their namespace-qualified name (`default` for a JSON document).
+    pub values: RSTypeMap,
+    /// The type parameters of the generic type aliases declared in this module,
+    /// keyed by their namespace-qualified name.
+    pub type_parameters: HashMap<String, Vec<TypeParameter>>,
+    /// The types imported from other modules, keyed by local name.
+    pub imports: HashMap<String, TypeMapping>,
+    /// The names exported from this module, keyed by public name.
+    pub exports: HashMap<String, TypeMapping>,
+    /// The aliases declared with `import local = Namespace.Entity`, keyed by qualified local name.
+    pub aliases: HashMap<String, String>,
+    /// The entity of `export = entity`; its members are the exports of this module.
+    pub export_assignment: Option<String>,
+    /// The global name declared with `export as namespace name` (UMD).
+    pub namespace_export: Option<String>,
+    /// The modules re-exported with `export * from "source"`.
+    pub star_exports: Vec<StarExport>,
+}
+
+/// A type parameter of a generic type alias, e.g. `T` in `type Page<T = User> = ...`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TypeParameter {
+    pub name: String,
+    /// The type used when no type argument is given.
+    pub default: Option<RSType>,
+}
+
+/// An `export * from "source"` declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StarExport {
+    /// The path to the re-exported module, or None if it could not be resolved.
+    pub original_module: Option<PathBuf>,
+    /// The module specifier as written in the declaration.
+    pub module_specifier: String,
+    /// The span of the declaration.
+    pub span: Span,
+}
+
+impl Module {
+    /// Returns the names of the top-level declarations, values and namespaces of this module.
+    pub fn top_level_names(&self) -> impl Iterator<Item = &str> {
+        self.types
+            .keys()
+            .chain(self.values.keys())
+            .map(|key| key.split('.').next().unwrap_or(key))
+    }
+
+    /// Returns true if a namespace with the qualified `name` is declared in this module.
+    pub fn has_namespace(&self, name: &str) -> bool {
+        let prefix = format!("{}.", name);
+        self.types.keys().any(|key| key.starts_with(&prefix))
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use lazy_static::lazy_static;
 use oxc_parser::ParseOptions;
 use oxc_resolver::{EnforceExtension, ResolveOptions};
@@ -9,16 +11,66 @@ lazy_static! {
         ..ParseOptions::default()
     };
     static ref DEFAULT_RESOLVE_OPTIONS: ResolveOptions = ResolveOptions {
-        extensions: vec![".d.ts".into(), ".ts".into(), "".into()],
-        main_fields: vec!["types".into(), "typings".into()],
+        // Declaration files are preferred over sources, and ESM/CJS-specific files are only
+        // found through their `.mjs`/`.cjs` specifiers, like with tsc. JavaScript modules come
+        // last, for packages that only ship JSDoc types.
+        extensions: vec![
+            ".d.ts".into(),
+            ".ts".into(),
+            ".tsx".into(),
+            ".js".into(),
+            "".into()
+        ],
+        main_fields: vec!["types".into(), "typings".into(), "main".into()],
+        // The conditions tsc matches in package.json `exports` when importing from ES modules.
+        condition_names: vec!["types".into(), "import".into(), "node".into()],
+        // `import "./foo.js"` refers to the declarations or sources of `foo.js`,
+        // and likewise for `.jsx`, `.mjs` and `.cjs`.
+        extension_alias: vec![
+            (
+                ".js".into(),
+                vec![".d.ts".into(), ".ts".into(), ".tsx".into(), ".js".into()]
+            ),
+            (
+                ".jsx".into(),
+                vec![".d.ts".into(), ".tsx".into(), ".jsx".into()]
+            ),
+            (
+                ".mjs".into(),
+                vec![".d.mts".into(), ".mts".into(), ".mjs".into()]
+            ),
+            (
+                ".cjs".into(),
+                vec![".d.cts".into(), ".cts".into(), ".cjs".into()]
+            ),
+        ],
         enforce_extension: EnforceExtension::Enabled,
         ..ResolveOptions::default()
     };
 }
 
+/// How intersections of object types like `A & B & { extra: string }` are converted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) enum IntersectionStyle {
+    /// One struct with the fields of all members.
+    #[default]
+    Merge,
+    /// A struct with a `#[serde(flatten)]` field per named member,
+    /// and the fields of the inline object types.
+    Flatten,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct TypeScriptOptions {
+    /// Drop fields whose type is unimplemented instead of keeping them as `RSType::Unimplemented`.
     pub ignore_unimplemented: bool,
+    /// Fail with a `DiagnosticsError` listing every lossy or unimplemented conversion.
+    pub strict: bool,
+    /// The `tsconfig.json` whose `paths`, `baseUrl` and `references` are used to resolve modules.
+    /// Defaults to the nearest `tsconfig.json` in the directory of the entrypoint or its parents.
+    pub tsconfig: Option<PathBuf>,
+    /// How intersections of object types like `A & B` are converted.
+    pub intersection_style: IntersectionStyle,
     pub parse_options: ParseOptions,
     pub resolve_options: ResolveOptions,
 }
@@ -27,6 +79,9 @@ impl Default for TypeScriptOptions {
     fn default() -> Self {
         Self {
             ignore_unimplemented: true,
+            strict: false,
+            tsconfig: None,
+            intersection_style: IntersectionStyle::default(),
             parse_options: *DEFAULT_PARSE_OPTIONS,
             resolve_options: DEFAULT_RESOLVE_OPTIONS.clone(),
         }
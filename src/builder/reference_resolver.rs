@@ -1,49 +1,1880 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use oxc_diagnostics::OxcDiagnostic;
+use oxc_span::Span;
+use tracing::{debug, trace};
 
 use crate::rs_types::*;
 
-use super::TypeScriptToRustVisitor;
+use super::{
+    make_rs_type::{make_field_type, make_union_or_option_type},
+    module::{Module, StarExport, GLOBAL_SCOPE},
+    options::IntersectionStyle,
+    visitor::{OriginalName, TypeMapping},
+    warnings::{ConversionWarning, Fallback},
+    TypeScriptToRustBuilder,
+};
+
+/// Gives up on import/export chains longer than this (e.g. circular re-exports).
+const MAX_RESOLUTION_DEPTH: usize = 32;
+/// The maximum nesting of instantiations of a generic type alias with different arguments,
+/// e.g. of `DeepPartial<T[K]>` in `DeepPartial<T>`.
+const MAX_NESTED_INSTANTIATIONS: usize = 8;
+/// The maximum number of strings a template literal type is expanded to.
+const MAX_TEMPLATE_EXPANSIONS: usize = 256;
+/// The maximum number of intersections an intersection with unions is distributed to.
+const MAX_INTERSECTION_COMBINATIONS: usize = 64;
 
 pub(crate) trait ReferenceResolver {
+    fn resolve_star_exports(&mut self);
     fn resolve_references(&mut self) -> HashSet<RSReference>;
+    fn merge_globals(&mut self);
 }
 
-impl ReferenceResolver for TypeScriptToRustVisitor {
+impl ReferenceResolver for TypeScriptToRustBuilder {
+    /// Expands the `export * from "source"` declarations of all visited modules into
+    /// re-export `TypeMapping`s. Explicit exports and local declarations shadow star exports,
+    /// and names exported by more than one star export are ambiguous and left out.
+    fn resolve_star_exports(&mut self) {
+        let linker = Linker::new(&self.modules, self.options.intersection_style);
+        let mut mappings: Vec<(PathBuf, TypeMapping)> = Vec::new();
+        let mut warnings: Vec<ConversionWarning> = Vec::new();
+
+        for (module_path, module) in &self.modules {
+            let shadowed: HashSet<&str> = module
+                .exports
+                .keys()
+                .map(String::as_str)
+                .chain(module.top_level_names())
+                .collect();
+
+            let mut candidates: BTreeMap<String, Vec<(&StarExport, &PathBuf)>> = BTreeMap::new();
+            for star_export in &module.star_exports {
+                let Some(original_module) = &star_export.original_module else {
+                    continue;
+                };
+                for name in linker.exported_names(original_module, &mut HashSet::new()) {
+                    if name != "default" && !shadowed.contains(name.as_str()) {
+                        candidates
+                            .entry(name)
+                            .or_default()
+                            .push((star_export, original_module));
+                    }
+                }
+            }
+
+            for (name, star_exports) in candidates {
+                let mut resolutions: Vec<Option<Resolution>> = Vec::new();
+                for (_, original_module) in &star_exports {
+                    let resolution = linker.resolve_export(original_module, &[&name], 0);
+                    if !resolutions.contains(&resolution) {
+                        resolutions.push(resolution);
+                    }
+                }
+
+                // Ambiguous star exports are left out, unless the name is re-exported explicitly.
+                if resolutions.len() > 1 {
+                    let what = format!(
+                        "`{}`, which is exported by more than one `export *` declaration",
+                        name
+                    );
+                    for (star_export, _) in &star_exports {
+                        warnings.push(ConversionWarning {
+                            module_path: module_path.clone(),
+                            span: star_export.span,
+                            construct: "ExportAllDeclaration".to_string(),
+                            fallback: Fallback::Dropped(what.clone()),
+                        });
+                    }
+                    continue;
+                }
+
+                let (star_export, original_module) = star_exports[0];
+                debug!("export {} from {:?} (export *)", name, original_module);
+                let mapping = TypeMapping {
+                    original_module: Some(original_module.clone()),
+                    module_specifier: Some(star_export.module_specifier.clone()),
+                    original_name: OriginalName::Named(name.clone()),
+                    local_name: name.clone(),
+                    public_name: name,
+                };
+                mappings.push((module_path.clone(), mapping));
+            }
+        }
+
+        for (module_path, mapping) in mappings {
+            if let Some(module) = self.modules.get_mut(&module_path) {
+                module.exports.insert(mapping.public_name.clone(), mapping);
+            }
+        }
+
+        warnings.sort_by_key(|warning| (warning.module_path.clone(), warning.span.start));
+        self.report_warnings(warnings);
+    }
+
+    /// Resolves the references of all visited modules to the modules declaring them.
+    /// Returns the references that could not be resolved.
     fn resolve_references(&mut self) -> HashSet<RSReference> {
-        let keys: Vec<_> = self.types.keys().cloned().collect();
-        println!("resolve_references: KEYS: {:#?}", keys);
+        let linker = Linker::new(&self.modules, self.options.intersection_style);
+        let mut unresolved: HashSet<RSReference> = HashSet::new();
+        let mut resolved_types: Vec<(PathBuf, Declarations, String, RSType)> = Vec::new();
 
-        let mut references: HashSet<RSReference> = HashSet::new();
+        for (module_path, module) in &self.modules {
+            trace!(
+                "resolve_references: {:?} KEYS: {:#?}",
+                module_path,
+                module.types.keys()
+            );
+            for declarations in [
+                Declarations::Types,
+                Declarations::Globals,
+                Declarations::Values,
+            ] {
+                for (name, rs_type) in declarations.of(module) {
+                    // References are looked up from the namespace of the declaration.
+                    let scope: Vec<&str> = name.split('.').collect();
+                    let scope = &scope[..scope.len() - 1];
+                    let resolved_type = resolve_type(rs_type, &mut |leaf| {
+                        let resolved = linker.resolve_leaf(module_path, scope, leaf, 0);
+                        collect_unresolved(&resolved, &mut unresolved);
+                        resolved
+                    });
+                    resolved_types.push((
+                        module_path.clone(),
+                        declarations,
+                        name.clone(),
+                        resolved_type,
+                    ));
+                }
+            }
+        }
+
+        // Generic type aliases are evaluated where they are instantiated.
+        let unevaluated = linker.unevaluated.take();
+        let mut warnings = linker.warnings.take();
+        for (module_path, declarations, name, resolved_type) in &mut resolved_types {
+            let module_path = &*module_path;
+            // A branded type alias is named after the alias, e.g. `UserId`.
+            if let (Declarations::Types | Declarations::Globals, RSType::Newtype(brand, _)) =
+                (&declarations, &mut *resolved_type)
+            {
+                *brand = name
+                    .rsplit_once('.')
+                    .map_or(name.as_str(), |(_, name)| name)
+                    .to_string();
+            }
+            let mut brand_fields: Vec<(String, Span)> = Vec::new();
+            drop_brand_fields(resolved_type, &mut brand_fields);
 
-        for name in keys {
-            if let Some(rs_type) = self.types.get(&name).cloned() {
-                let resolved_type = resolve_type(&rs_type, &self.types, &mut references);
-                if let Some(mut_ref_type) = self.types.get_mut(&name) {
-                    *mut_ref_type = resolved_type;
+            if matches!(declarations, Declarations::Values)
+                || self.modules[module_path].type_parameters.contains_key(name)
+            {
+                continue;
+            }
+            for (field_name, span) in brand_fields {
+                let construct = "TSPropertySignature".to_string();
+                warnings.insert(
+                    (module_path.clone(), span.start, construct.clone()),
+                    ConversionWarning {
+                        module_path: module_path.clone(),
+                        span,
+                        construct,
+                        fallback: Fallback::Dropped(format!(
+                            "field `{}`, which only brands an intersection",
+                            field_name
+                        )),
+                    },
+                );
+            }
+            let mut leaves: Vec<&RSType> = Vec::new();
+            collect_unevaluated(resolved_type, &mut leaves);
+            for leaf in leaves {
+                let Some((construct, span)) = unevaluated_construct(leaf) else {
+                    continue;
+                };
+                // Operators of other modules are inlined by instantiations and `typeof` queries.
+                let module_path = unevaluated
+                    .iter()
+                    .find(|(_, recorded)| recorded == leaf)
+                    .map_or(module_path, |(module_path, _)| module_path);
+                warnings.insert(
+                    (module_path.clone(), span.start, construct.to_string()),
+                    ConversionWarning {
+                        module_path: module_path.clone(),
+                        span,
+                        construct: construct.to_string(),
+                        fallback: Fallback::Unevaluated,
+                    },
+                );
+            }
+        }
+
+        for (module_path, declarations, name, resolved_type) in resolved_types {
+            if let Some(module) = self.modules.get_mut(&module_path) {
+                declarations.of_mut(module).insert(name, resolved_type);
+            }
+        }
+        self.report_warnings(warnings.into_values().collect());
+
+        debug!("unresolved references: {:?}", unresolved);
+
+        unresolved
+    }
+
+    /// Merges the global declarations of all modules into the `GLOBAL_SCOPE` pseudo-module.
+    /// Interfaces declared in more than one module are merged into one struct,
+    /// and for other conflicting declarations the first one wins.
+    fn merge_globals(&mut self) {
+        let mut global_scope = Module::default();
+        let mut diagnostics: Vec<(PathBuf, OxcDiagnostic)> = Vec::new();
+        let module_paths: BTreeSet<&PathBuf> = self.modules.keys().collect();
+
+        for module_path in module_paths {
+            for (name, rs_type) in &self.modules[module_path].globals {
+                match (global_scope.types.get_mut(name), rs_type) {
+                    (None, _) => {
+                        global_scope.types.insert(name.clone(), rs_type.clone());
+                    }
+                    (Some(RSType::Struct(merged)), RSType::Struct(declared)) => {
+                        merged.fields.extend(declared.fields.clone());
+                    }
+                    (Some(_), _) => diagnostics.push((
+                        module_path.clone(),
+                        OxcDiagnostic::warn(format!(
+                            "Conflicting declarations of the global type `{}` in {}",
+                            name,
+                            module_path.display()
+                        ))
+                        .with_help("The first declaration is used."),
+                    )),
                 }
             }
         }
 
-        references
+        for (module_path, diagnostic) in diagnostics {
+            self.report(&module_path, diagnostic);
+        }
+
+        self.modules
+            .insert(PathBuf::from(GLOBAL_SCOPE), global_scope);
+    }
+}
+
+/// The declarations of a module whose references are resolved.
+#[derive(Debug, Clone, Copy)]
+enum Declarations {
+    Types,
+    Globals,
+    Values,
+}
+
+impl Declarations {
+    fn of(self, module: &Module) -> &RSTypeMap {
+        match self {
+            Declarations::Types => &module.types,
+            Declarations::Globals => &module.globals,
+            Declarations::Values => &module.values,
+        }
+    }
+
+    fn of_mut(self, module: &mut Module) -> &mut RSTypeMap {
+        match self {
+            Declarations::Types => &mut module.types,
+            Declarations::Globals => &mut module.globals,
+            Declarations::Values => &mut module.values,
+        }
     }
 }
 
-fn resolve_type(
-    rs_type: &RSType,
-    type_map: &RSTypeMap,
-    references: &mut HashSet<RSReference>,
-) -> RSType {
+/// What a name resolves to in the module graph.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Resolution {
+    /// A type declared in a module under its namespace-qualified name.
+    Declaration { module_path: PathBuf, name: String },
+    /// A value declared in a module, e.g. `declare const config: Config` or a JSON document.
+    Value { module_path: PathBuf, name: String },
+    /// A module, or a namespace declared in a module if `name` is Some.
+    Namespace {
+        module_path: PathBuf,
+        name: Option<String>,
+    },
+    /// A type from a module specifier that could not be resolved.
+    External {
+        name: String,
+        module_specifier: String,
+    },
+}
+
+/// Resolves names through the scopes, imports and exports of the visited modules.
+pub(crate) struct Linker<'m> {
+    modules: &'m HashMap<PathBuf, Module>,
+    /// The modules declaring a global name with `export as namespace name`.
+    namespace_exports: HashMap<&'m str, &'m Path>,
+    /// The namespace-qualified names of the types declared in the global scope.
+    globals: HashSet<&'m str>,
+    /// The generic type aliases being instantiated with their arguments, to stop at recursive
+    /// instantiations with the same arguments.
+    instantiating: RefCell<Vec<(PathBuf, String, Vec<RSType>)>>,
+    /// The declarations being resolved, to stop at self-referencing declarations like
+    /// `interface Node { kind: keyof Node }`, and whether they were referenced recursively.
+    declaring: RefCell<Vec<((PathBuf, String), bool)>>,
+    /// The resolved declared types, except those of recursive declarations, which depend
+    /// on the declaration that was resolved first.
+    declared_types: RefCell<HashMap<(PathBuf, String), Option<RSType>>>,
+    intersection_style: IntersectionStyle,
+    /// The lossy conversions found when evaluating types, keyed by module, span and subject,
+    /// as the same type may be evaluated more than once.
+    warnings: RefCell<BTreeMap<(PathBuf, u32, String), ConversionWarning>>,
+    /// The type operators that could not be evaluated and the modules declaring them,
+    /// to report those left in the resolved types.
+    unevaluated: RefCell<Vec<(PathBuf, RSType)>>,
+}
+
+impl<'m> Linker<'m> {
+    pub fn new(
+        modules: &'m HashMap<PathBuf, Module>,
+        intersection_style: IntersectionStyle,
+    ) -> Self {
+        let namespace_exports = modules
+            .iter()
+            .filter_map(|(path, module)| {
+                let name = module.namespace_export.as_deref()?;
+                Some((name, path.as_path()))
+            })
+            .collect();
+        let globals = modules
+            .values()
+            .flat_map(|module| module.globals.keys().map(String::as_str))
+            .collect();
+        Self {
+            modules,
+            namespace_exports,
+            globals,
+            instantiating: RefCell::default(),
+            declaring: RefCell::default(),
+            declared_types: RefCell::default(),
+            intersection_style,
+            warnings: RefCell::default(),
+            unevaluated: RefCell::default(),
+        }
+    }
+
+    /// Resolves a reference found in the namespace `scope` of a module.
+    /// Returns the reference unchanged if it cannot be resolved.
+    pub fn resolve_reference(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        reference: &RSReference,
+    ) -> RSReference {
+        match self.resolve_reference_name(module_path, scope, reference) {
+            Some(Resolution::Declaration { module_path, name }) => {
+                RSReference::Resolved { name, module_path }
+            }
+            Some(Resolution::External {
+                name,
+                module_specifier,
+            }) => RSReference::Unresolved {
+                name,
+                module_specifier: Some(module_specifier),
+            },
+            _ => reference.clone(),
+        }
+    }
+
+    /// Resolves a reference or type operator found in the namespace `scope` of a module.
+    /// `typeof` queries are replaced with the (resolved) type of the value, `keyof` operators
+    /// with an enum of the property names, indexed access types with the type of the property
+    /// or element, mapped types with a struct or map, generic type aliases with their
+    /// instantiation and conditional types with a branch, if they are known.
+    pub fn resolve_leaf(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        leaf: &RSType,
+        depth: usize,
+    ) -> RSType {
+        let resolved = self.evaluate_leaf(module_path, scope, leaf, depth);
+        if unevaluated_construct(&resolved).is_some() {
+            let mut unevaluated = self.unevaluated.borrow_mut();
+            if !unevaluated
+                .iter()
+                .any(|(_, recorded)| recorded == &resolved)
+            {
+                unevaluated.push((module_path.to_path_buf(), resolved.clone()));
+            }
+        }
+        resolved
+    }
+
+    fn evaluate_leaf(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        leaf: &RSType,
+        depth: usize,
+    ) -> RSType {
+        match leaf {
+            RSType::Reference(reference) => {
+                RSType::Reference(self.resolve_reference(module_path, scope, reference))
+            }
+            RSType::TypeQuery(reference, _) => self
+                .resolve_type_query(module_path, scope, reference, depth)
+                .unwrap_or_else(|| leaf.clone()),
+            RSType::KeyOf(inner, span) => {
+                let inner = resolve_type(inner, &mut |leaf| {
+                    self.resolve_leaf(module_path, scope, leaf, depth + 1)
+                });
+                match self.property_names(&inner, depth) {
+                    Some(names) => RSType::Enum(RSEnum {
+                        option: false,
+                        variants: names
+                            .into_iter()
+                            .map(|name| RSType::EnumVariant(RSEnumVariant::StringLiteral(name)))
+                            .collect(),
+                    }),
+                    None => RSType::KeyOf(Box::new(inner), *span),
+                }
+            }
+            RSType::IndexedAccess(object, index, span) => {
+                let mut resolve =
+                    |leaf: &RSType| self.resolve_leaf(module_path, scope, leaf, depth + 1);
+                let object = resolve_type(object, &mut resolve);
+                let index = resolve_type(index, &mut resolve);
+                self.indexed_type(&object, &index, depth)
+                    .unwrap_or_else(|| {
+                        RSType::IndexedAccess(Box::new(object), Box::new(index), *span)
+                    })
+            }
+            RSType::Instantiation(reference, arguments, span) => {
+                let arguments: Vec<RSType> = arguments
+                    .iter()
+                    .map(|argument| {
+                        resolve_type(argument, &mut |leaf| {
+                            self.resolve_leaf(module_path, scope, leaf, depth + 1)
+                        })
+                    })
+                    .collect();
+                self.instantiate(module_path, scope, reference, &arguments, depth)
+                    .unwrap_or_else(|| {
+                        // Other generic types are converted to the union of their type arguments.
+                        let union = make_union_or_option_type(&arguments);
+                        self.report_substituted_instantiation(
+                            module_path,
+                            *span,
+                            reference,
+                            &union,
+                        );
+                        union
+                    })
+            }
+            RSType::Conditional(conditional) => {
+                self.conditional_type(module_path, scope, conditional, depth)
+            }
+            RSType::Intersection(members, span) => {
+                let members: Vec<RSType> = members
+                    .iter()
+                    .map(|member| {
+                        resolve_type(member, &mut |leaf| {
+                            self.resolve_leaf(module_path, scope, leaf, depth + 1)
+                        })
+                    })
+                    .collect();
+                self.intersection_type(module_path, &members, *span, depth)
+                    .unwrap_or(RSType::Intersection(members, *span))
+            }
+            RSType::TemplateLiteral(template) => {
+                let types: Vec<RSType> = template
+                    .types
+                    .iter()
+                    .map(|rs_type| {
+                        resolve_type(rs_type, &mut |leaf| {
+                            self.resolve_leaf(module_path, scope, leaf, depth + 1)
+                        })
+                    })
+                    .collect();
+                self.template_literal_type(&template.quasis, types, depth)
+            }
+            RSType::Mapped(mapped) => {
+                let keys = resolve_type(&mapped.keys, &mut |leaf| {
+                    self.resolve_leaf(module_path, scope, leaf, depth + 1)
+                });
+                self.mapped_type(module_path, scope, mapped, &keys, depth)
+                    .unwrap_or_else(|| {
+                        RSType::Mapped(RSMappedType {
+                            keys: Box::new(keys),
+                            ..mapped.clone()
+                        })
+                    })
+            }
+            _ => leaf.clone(),
+        }
+    }
+
+    /// Returns the sorted property names of a resolved type for `keyof`: the fields of a struct,
+    /// or the fields common to all members of a union. Returns None if they are not known.
+    fn property_names(&self, rs_type: &RSType, depth: usize) -> Option<BTreeSet<String>> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        match rs_type {
+            // Brand fields are not properties that can be selected.
+            RSType::Struct(RSStruct { fields }) => Some(
+                fields
+                    .iter()
+                    .filter(|(_, field_type)| !matches!(field_type, RSType::Brand(_, _)))
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+            ),
+            RSType::Flatten(_) => {
+                let rs_struct = self.struct_fields(rs_type, depth)?;
+                self.property_names(&RSType::Struct(rs_struct), depth + 1)
+            }
+            RSType::Reference(RSReference::Resolved { name, module_path }) => {
+                let declared_type = self.declared_type(module_path, name, depth)?;
+                self.property_names(&declared_type, depth + 1)
+            }
+            RSType::EnumVariant(RSEnumVariant::RSType(inner)) => {
+                self.property_names(inner, depth + 1)
+            }
+            RSType::Enum(RSEnum { variants, .. }) => variants
+                .iter()
+                .map(|variant| self.property_names(variant, depth + 1))
+                .reduce(|names, variant_names| {
+                    Some(names?.intersection(&variant_names?).cloned().collect())
+                })?,
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the properties or elements of a resolved type selected by `index`:
+    /// a field of a struct for a string literal, the element type of an array for a number,
+    /// an element of a tuple for a numeric literal and the union of its elements for `number`,
+    /// and the union of the selected types for a union of indices.
+    /// Returns None if the type is not known.
+    fn indexed_type(&self, object: &RSType, index: &RSType, depth: usize) -> Option<RSType> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        match (object, index) {
+            (
+                _,
+                RSType::Enum(RSEnum {
+                    option: false,
+                    variants,
+                }),
+            ) => {
+                let variants = variants
+                    .iter()
+                    .map(|variant| self.indexed_type(object, variant, depth + 1))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(make_union_type(variants))
+            }
+            (RSType::Reference(RSReference::Resolved { name, module_path }), _) => {
+                let declared_type = self.declared_type(module_path, name, depth)?;
+                self.indexed_type(&declared_type, index, depth + 1)
+            }
+            (RSType::EnumVariant(RSEnumVariant::RSType(inner)), _) => {
+                self.indexed_type(inner, index, depth + 1)
+            }
+            // `(A | B)['kind']` selects the property of every member of the union.
+            (
+                RSType::Enum(RSEnum {
+                    option: false,
+                    variants,
+                }),
+                _,
+            ) => {
+                let variants = variants
+                    .iter()
+                    .map(|variant| self.indexed_type(variant, index, depth + 1))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(make_union_type(variants))
+            }
+            (
+                RSType::Struct(RSStruct { fields }),
+                RSType::EnumVariant(RSEnumVariant::StringLiteral(name)),
+            ) => fields.get(name).cloned(),
+            (RSType::Flatten(_), RSType::EnumVariant(RSEnumVariant::StringLiteral(name))) => {
+                self.struct_fields(object, depth)?.fields.remove(name)
+            }
+            (
+                RSType::Vec(element_type),
+                RSType::EnumVariant(RSEnumVariant::NumericLiteral(_))
+                | RSType::Primitive(RSPrimitive::F64 | RSPrimitive::I32 | RSPrimitive::I64),
+            ) => Some(element_type.as_ref().clone()),
+            (
+                RSType::Tuple(elements),
+                RSType::EnumVariant(RSEnumVariant::NumericLiteral(index)),
+            ) => elements.get(index.parse::<usize>().ok()?).cloned(),
+            (
+                RSType::Tuple(elements),
+                RSType::Primitive(RSPrimitive::F64 | RSPrimitive::I32 | RSPrimitive::I64),
+            ) => Some(make_union_type(elements.clone())),
+            _ => None,
+        }
+    }
+
+    /// Evaluates a mapped type over its resolved `keys`: a struct with a field per key for
+    /// string literal keys, or a map for `string` or `number` keys. Returns None otherwise.
+    fn mapped_type(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        mapped: &RSMappedType,
+        keys: &RSType,
+        depth: usize,
+    ) -> Option<RSType> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        let mut resolve_value = |key: &RSType| {
+            let value = mapped.value.substitute(&mapped.key_name, key);
+            let value = resolve_type(&value, &mut |leaf| {
+                self.resolve_leaf(module_path, scope, leaf, depth + 1)
+            });
+            // Like the types of fields, a promise is converted to the type of its value.
+            let value = match value {
+                RSType::Promise(inner) => *inner,
+                value => value,
+            };
+            match (mapped.optional, value) {
+                (Some(true), value) => make_field_type(value, true),
+                // `-?` makes the properties required.
+                (Some(false), RSType::Option(inner)) => *inner,
+                (Some(false), RSType::Enum(RSEnum { variants, .. })) => RSType::Enum(RSEnum {
+                    option: false,
+                    variants,
+                }),
+                (_, value) => value,
+            }
+        };
+
+        if let RSType::Primitive(RSPrimitive::String | RSPrimitive::F64) = keys {
+            let value = resolve_value(keys);
+            return Some(RSType::HashMap(Box::new(keys.clone()), Box::new(value)));
+        }
+
+        let fields = self
+            .literal_keys(keys, depth)?
+            .into_iter()
+            .map(|key| {
+                let value = resolve_value(&RSType::EnumVariant(RSEnumVariant::StringLiteral(
+                    key.clone(),
+                )));
+                (key, value)
+            })
+            .collect();
+        Some(RSType::Struct(RSStruct { fields }))
+    }
+
+    /// Returns the property names of a resolved union of string or number literals,
+    /// e.g. `"a" | "b"` or `keyof Options` after evaluation. Returns None if they are not known.
+    fn literal_keys(&self, keys: &RSType, depth: usize) -> Option<Vec<String>> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        match keys {
+            RSType::EnumVariant(RSEnumVariant::StringLiteral(key))
+            | RSType::EnumVariant(RSEnumVariant::NumericLiteral(key)) => Some(vec![key.clone()]),
+            RSType::EnumVariant(RSEnumVariant::RSType(inner)) => {
+                self.literal_keys(inner, depth + 1)
+            }
+            RSType::Enum(RSEnum {
+                option: false,
+                variants,
+            }) => variants
+                .iter()
+                .map(|variant| self.literal_keys(variant, depth + 1))
+                .collect::<Option<Vec<_>>>()
+                .map(|keys| keys.concat()),
+            RSType::Reference(RSReference::Resolved { name, module_path }) => {
+                let declared_type = self.declared_type(module_path, name, depth)?;
+                self.literal_keys(&declared_type, depth + 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the type declared with the namespace-qualified `name` in a module,
+    /// with its references resolved from that module. Global interfaces declared in more than
+    /// one module are merged like in `merge_globals`.
+    fn declared_type(&self, module_path: &Path, name: &str, depth: usize) -> Option<RSType> {
+        let declaration = (module_path.to_path_buf(), name.to_string());
+        if let Some(declared_type) = self.declared_types.borrow().get(&declaration) {
+            return declared_type.clone();
+        }
+        {
+            let mut declaring = self.declaring.borrow_mut();
+            if let Some(index) = declaring
+                .iter()
+                .position(|(other, _)| *other == declaration)
+            {
+                debug!("recursive declaration of {}", name);
+                for (_, recursive) in &mut declaring[index..] {
+                    *recursive = true;
+                }
+                return None;
+            }
+            declaring.push((declaration.clone(), false));
+        }
+
+        let scope: Vec<&str> = name.split('.').collect();
+        let scope = &scope[..scope.len() - 1];
+        let mut merged: Option<RSType> = None;
+        for (module_path, declared_type) in self.declarations(module_path, name) {
+            let declared_type = resolve_type(declared_type, &mut |leaf| {
+                self.resolve_leaf(module_path, scope, leaf, depth + 1)
+            });
+            merged = match (merged, declared_type) {
+                (None, declared_type) => Some(declared_type),
+                (Some(RSType::Struct(mut merged)), RSType::Struct(declared)) => {
+                    merged.fields.extend(declared.fields);
+                    Some(RSType::Struct(merged))
+                }
+                (merged, _) => merged,
+            };
+        }
+
+        let recursive = self
+            .declaring
+            .borrow_mut()
+            .pop()
+            .is_some_and(|(_, recursive)| recursive);
+        if !recursive {
+            self.declared_types
+                .borrow_mut()
+                .insert(declaration, merged.clone());
+        }
+        merged
+    }
+
+    /// Returns the module declaring the type with the namespace-qualified `name`
+    /// and its unresolved declaration, or the first of the global declarations.
+    fn declaration(&self, module_path: &Path, name: &str) -> Option<(&'m Path, &'m RSType)> {
+        self.declarations(module_path, name).into_iter().next()
+    }
+
+    /// Returns the modules declaring the type with the namespace-qualified `name` and their
+    /// unresolved declarations. The global scope is only merged after linking, so global
+    /// declarations are looked up in all modules, in the order of their paths.
+    fn declarations(&self, module_path: &Path, name: &str) -> Vec<(&'m Path, &'m RSType)> {
+        match module_path.as_os_str() == GLOBAL_SCOPE {
+            true => {
+                let mut declarations: Vec<(&'m Path, &'m RSType)> = self
+                    .modules
+                    .iter()
+                    .filter_map(|(module_path, module)| {
+                        Some((module_path.as_path(), module.globals.get(name)?))
+                    })
+                    .collect();
+                declarations.sort_by_key(|(module_path, _)| *module_path);
+                declarations
+            }
+            false => self
+                .modules
+                .get_key_value(module_path)
+                .and_then(|(module_path, module)| {
+                    Some((module_path.as_path(), module.types.get(name)?))
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Instantiates a generic type alias: substitutes the type arguments (or the defaults)
+    /// for its type parameters and resolves it from the module declaring it.
+    /// Returns None if the reference is not a generic type alias or it is instantiated
+    /// recursively.
+    fn instantiate(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        reference: &RSReference,
+        arguments: &[RSType],
+        depth: usize,
+    ) -> Option<RSType> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        let Resolution::Declaration { module_path, name } =
+            self.resolve_reference_name(module_path, scope, reference)?
+        else {
+            return None;
+        };
+        let (module_path, declared_type) = self.declaration(&module_path, &name)?;
+        let type_parameters = self.modules.get(module_path)?.type_parameters.get(&name)?;
+
+        let instantiation = (module_path.to_path_buf(), name.clone(), arguments.to_vec());
+        let instantiating = self.instantiating.borrow();
+        let nested = instantiating
+            .iter()
+            .filter(|(path, instantiated, _)| *path == instantiation.0 && *instantiated == name)
+            .count();
+        let recursive =
+            nested >= MAX_NESTED_INSTANTIATIONS || instantiating.contains(&instantiation);
+        drop(instantiating);
+        if recursive {
+            debug!("recursive instantiation of {}", name);
+            // The declarations being resolved depend on where the recursion was stopped.
+            for (_, recursive) in self.declaring.borrow_mut().iter_mut() {
+                *recursive = true;
+            }
+            return None;
+        }
+
+        let mut substitutions: Vec<(&str, RSType)> = Vec::new();
+        for (i, parameter) in type_parameters.iter().enumerate() {
+            let argument = match (arguments.get(i), &parameter.default) {
+                (Some(argument), _) => argument.clone(),
+                // Defaults may refer to the preceding type parameters.
+                (None, Some(default)) => substitutions
+                    .iter()
+                    .fold(default.clone(), |default, (name, argument)| {
+                        default.substitute(name, argument)
+                    }),
+                (None, None) => RSType::JSONValue,
+            };
+            substitutions.push((&parameter.name, argument));
+        }
+        let instantiated_type = substitutions
+            .iter()
+            .fold(declared_type.clone(), |rs_type, (name, argument)| {
+                rs_type.substitute(name, argument)
+            });
+
+        self.instantiating.borrow_mut().push(instantiation);
+        let declaration_scope: Vec<&str> = name.split('.').collect();
+        let declaration_scope = &declaration_scope[..declaration_scope.len() - 1];
+        let instantiated_type = resolve_type(&instantiated_type, &mut |leaf| {
+            self.resolve_leaf(module_path, declaration_scope, leaf, depth + 1)
+        });
+        self.instantiating.borrow_mut().pop();
+        Some(instantiated_type)
+    }
+
+    /// Evaluates a conditional type with its check and extends types resolved. A distributive
+    /// conditional type is evaluated for each member of a union. The result is left as a
+    /// conditional type if it cannot be decided whether the check type extends the other.
+    fn conditional_type(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        conditional: &RSConditionalType,
+        depth: usize,
+    ) -> RSType {
+        let mut resolve = |leaf: &RSType| self.resolve_leaf(module_path, scope, leaf, depth + 1);
+        let check_type = resolve_type(&conditional.check_type, &mut resolve);
+        let extends_type = resolve_type(&conditional.extends_type, &mut resolve);
+
+        let evaluated = match &conditional.distributive_parameter {
+            Some(parameter) => self
+                .union_members(&check_type, depth)
+                .iter()
+                .map(|member| {
+                    let branch =
+                        self.conditional_branch(conditional, member, &extends_type, depth)?;
+                    Some(branch.substitute(parameter, member))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(make_union_type),
+            None => self.conditional_branch(conditional, &check_type, &extends_type, depth),
+        };
+        match evaluated {
+            Some(evaluated) => resolve_type(&evaluated, &mut |leaf| {
+                self.resolve_leaf(module_path, scope, leaf, depth + 1)
+            }),
+            None => RSType::Conditional(RSConditionalType {
+                check_type: Box::new(check_type),
+                extends_type: Box::new(extends_type),
+                ..conditional.clone()
+            }),
+        }
+    }
+
+    /// Evaluates an intersection of resolved types, distributed over the unions among them:
+    /// `(A | B) & C` is `(A & C) | (B & C)`. Returns None if it cannot be evaluated.
+    fn intersection_type(
+        &self,
+        module_path: &Path,
+        members: &[RSType],
+        span: Span,
+        depth: usize,
+    ) -> Option<RSType> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        let alternatives: Vec<Vec<RSType>> = members
+            .iter()
+            .map(|member| self.union_members(member, depth))
+            .collect();
+        let combinations = alternatives.iter().try_fold(1usize, |count, alternatives| {
+            count
+                .checked_mul(alternatives.len())
+                .filter(|&count| count <= MAX_INTERSECTION_COMBINATIONS)
+        });
+        if combinations.is_none() {
+            return self.intersect(module_path, members.to_vec(), span, depth);
+        }
+
+        let mut combinations: Vec<Vec<RSType>> = vec![Vec::new()];
+        for alternatives in &alternatives {
+            combinations = combinations
+                .iter()
+                .flat_map(|combination| {
+                    alternatives.iter().map(move |alternative| {
+                        let mut combination = combination.clone();
+                        combination.push(alternative.clone());
+                        combination
+                    })
+                })
+                .collect();
+        }
+        let intersections = combinations
+            .into_iter()
+            .map(|combination| self.intersect(module_path, combination, span, depth))
+            .collect::<Option<Vec<_>>>()?;
+        Some(make_union_type(intersections))
+    }
+
+    /// Intersects resolved types that are not unions. Object types are merged into a struct,
+    /// or composed into a `Flatten` struct with `IntersectionStyle::Flatten`, and the narrowest
+    /// of other types is kept, e.g. `"a"` of `"a" & string`. Disjoint types are `never`.
+    fn intersect(
+        &self,
+        module_path: &Path,
+        members: Vec<RSType>,
+        span: Span,
+        depth: usize,
+    ) -> Option<RSType> {
+        let mut objects: Vec<(RSType, RSStruct)> = Vec::new();
+        let mut others: Vec<RSType> = Vec::new();
+        for member in members {
+            match member {
+                // `unknown & T` is `T`, and `{}` adds no fields.
+                RSType::JSONValue => {}
+                RSType::Struct(RSStruct { fields }) if fields.is_empty() => {}
+                RSType::Never => return Some(RSType::Never),
+                member => match self.struct_fields(&member, depth) {
+                    Some(rs_struct) => objects.push((member, rs_struct)),
+                    None => others.push(member),
+                },
+            }
+        }
+
+        // `object & T` is `T` for object types.
+        if !objects.is_empty() {
+            others.retain(|other| *other != RSType::Object);
+        }
+        // `null & T` is `never` for object types.
+        if !objects.is_empty()
+            && others.iter().any(|other| {
+                matches!(
+                    other,
+                    RSType::NullOrUndefined | RSType::EnumVariant(RSEnumVariant::NullLiteral)
+                )
+            })
+        {
+            return Some(RSType::Never);
+        }
+        match (objects.is_empty(), others.len()) {
+            (true, 0) => Some(RSType::JSONValue),
+            (true, _) => self.narrowest(others, depth),
+            (false, 0) => {
+                let mut merged: Vec<(String, RSType)> = Vec::new();
+                for (_, rs_struct) in &objects {
+                    let mut fields: Vec<_> = rs_struct.fields.iter().collect();
+                    fields.sort_by_key(|(name, _)| *name);
+                    for (name, field_type) in fields {
+                        match merged
+                            .iter_mut()
+                            .find(|(merged_name, _)| merged_name == name)
+                        {
+                            Some((_, merged_type)) => {
+                                match self.merge_field_types(merged_type, field_type, depth) {
+                                    Some(field_type) => *merged_type = field_type,
+                                    None => self.report_field_conflict(module_path, span, name),
+                                }
+                            }
+                            None => merged.push((name.clone(), field_type.clone())),
+                        }
+                    }
+                }
+                let merged = RSStruct {
+                    fields: merged.into_iter().collect(),
+                };
+
+                match self.intersection_style {
+                    IntersectionStyle::Merge => Some(RSType::Struct(merged)),
+                    IntersectionStyle::Flatten => {
+                        // Named types are flattened, and the inline object types merged
+                        // without the fields of the named types, which serde cannot
+                        // deserialize twice.
+                        let mut inline = RSStruct {
+                            fields: HashMap::new(),
+                        };
+                        let mut flattened: Vec<RSType> = Vec::new();
+                        let mut named_fields: HashSet<String> = HashSet::new();
+                        for (member, rs_struct) in objects {
+                            match member {
+                                RSType::Struct(_) => {
+                                    for name in rs_struct.fields.into_keys() {
+                                        let field_type = merged.fields[&name].clone();
+                                        inline.fields.insert(name, field_type);
+                                    }
+                                }
+                                member => {
+                                    named_fields.extend(rs_struct.fields.into_keys());
+                                    flattened.push(member);
+                                }
+                            }
+                        }
+                        inline.fields.retain(|name, _| !named_fields.contains(name));
+                        if !inline.fields.is_empty() {
+                            flattened.push(RSType::Struct(inline));
+                        }
+                        match flattened.len() {
+                            1 => flattened.pop(),
+                            _ => Some(RSType::Flatten(flattened)),
+                        }
+                    }
+                }
+            }
+            // A branded type, like `string & { readonly __brand: "UserId" }` or
+            // `string & { [brand]: "UserId" }`, whose object types only have fields prefixed
+            // with an underscore or brand fields. Other intersections of an object type
+            // with another type, like `string & { length: number }`, are not evaluated.
+            (false, _) => {
+                let mut brand_fields: Vec<_> = objects
+                    .iter()
+                    .flat_map(|(_, rs_struct)| &rs_struct.fields)
+                    .collect();
+                if !brand_fields.iter().all(|(name, field_type)| {
+                    name.starts_with('_') || matches!(field_type, RSType::Brand(_, _))
+                }) {
+                    return None;
+                }
+                brand_fields.sort_by_key(|(name, _)| *name);
+                // The brand is the string literal type of a field, e.g. `UserId`, or else
+                // the name of the field, e.g. `tag` of `{ __tag: unique symbol }`.
+                let brand = brand_fields
+                    .iter()
+                    .find_map(|(_, mut field_type)| {
+                        while let RSType::Option(inner) | RSType::Brand(inner, _) = field_type {
+                            field_type = inner;
+                        }
+                        match field_type {
+                            RSType::EnumVariant(RSEnumVariant::StringLiteral(brand)) => {
+                                Some(brand.clone())
+                            }
+                            _ => None,
+                        }
+                    })
+                    .unwrap_or_else(|| {
+                        brand_fields[0]
+                            .0
+                            .trim_start_matches(['_', '['])
+                            .trim_end_matches(']')
+                            .to_string()
+                    });
+                let inner = self.narrowest(others, depth)?;
+                Some(RSType::Newtype(brand, Box::new(inner)))
+            }
+        }
+    }
+
+    /// Returns the narrowest of types that are not object types, e.g. `"a"` of `"a" & string`,
+    /// or `never` if they are disjoint.
+    fn narrowest(&self, types: Vec<RSType>, depth: usize) -> Option<RSType> {
+        types.into_iter().map(Some).reduce(|narrowest, other| {
+            let (narrowest, other) = (narrowest?, other?);
+            let mut inferred = Vec::new();
+            match (
+                self.extends(&narrowest, &other, &mut inferred, depth + 1),
+                self.extends(&other, &narrowest, &mut inferred, depth + 1),
+            ) {
+                (Some(true), _) => Some(narrowest),
+                (_, Some(true)) => Some(other),
+                // `void & string` is not reduced.
+                (Some(false), Some(false))
+                    if narrowest != RSType::Unit && other != RSType::Unit =>
+                {
+                    Some(RSType::Never)
+                }
+                _ => None,
+            }
+        })?
+    }
+
+    /// Returns the struct of an object type, also through a type alias or interface.
+    fn struct_fields(&self, rs_type: &RSType, depth: usize) -> Option<RSStruct> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        match rs_type {
+            RSType::Struct(rs_struct) => Some(rs_struct.clone()),
+            RSType::Flatten(members) => {
+                let mut fields = HashMap::new();
+                for member in members {
+                    fields.extend(self.struct_fields(member, depth + 1)?.fields);
+                }
+                Some(RSStruct { fields })
+            }
+            RSType::Reference(RSReference::Resolved { name, module_path }) => {
+                let declared_type = self.declared_type(module_path, name, depth)?;
+                self.struct_fields(&declared_type, depth + 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the type of a field declared by more than one member of an intersection,
+    /// e.g. the required `string` of `{ a?: string } & { a: string }`.
+    /// Returns None if the types conflict.
+    fn merge_field_types(&self, first: &RSType, second: &RSType, depth: usize) -> Option<RSType> {
+        if first == second {
+            return Some(first.clone());
+        }
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        match (first, second) {
+            (RSType::Option(first), RSType::Option(second)) => Some(RSType::Option(Box::new(
+                self.merge_field_types(first, second, depth + 1)?,
+            ))),
+            // A field that is optional in one member and required in another is required.
+            (RSType::Option(first), second) | (second, RSType::Option(first)) => {
+                self.merge_field_types(first, second, depth + 1)
+            }
+            _ => {
+                if let (Some(first), Some(second)) = (
+                    self.struct_fields(first, depth),
+                    self.struct_fields(second, depth),
+                ) {
+                    let mut fields = first.fields;
+                    for (name, second_type) in second.fields {
+                        let field_type = match fields.get(&name) {
+                            Some(first_type) => {
+                                self.merge_field_types(first_type, &second_type, depth + 1)?
+                            }
+                            None => second_type,
+                        };
+                        fields.insert(name, field_type);
+                    }
+                    return Some(RSType::Struct(RSStruct { fields }));
+                }
+                let mut inferred = Vec::new();
+                match (
+                    self.extends(first, second, &mut inferred, depth + 1),
+                    self.extends(second, first, &mut inferred, depth + 1),
+                ) {
+                    (Some(true), _) => Some(first.clone()),
+                    (_, Some(true)) => Some(second.clone()),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Reports a field with conflicting types in the members of an intersection.
+    /// The type of the first member declaring the field is used.
+    fn report_field_conflict(&self, module_path: &Path, span: Span, name: &str) {
+        let what = format!("conflicting type of the field `{}` in later members", name);
+        self.warnings.borrow_mut().insert(
+            (module_path.to_path_buf(), span.start, name.to_string()),
+            ConversionWarning {
+                module_path: module_path.to_path_buf(),
+                span,
+                construct: "TSIntersectionType".to_string(),
+                fallback: Fallback::Dropped(what),
+            },
+        );
+    }
+
+    /// Records that a generic type that is not a known type alias, e.g. `Set<User>`, was
+    /// converted to the union of its type arguments.
+    fn report_substituted_instantiation(
+        &self,
+        module_path: &Path,
+        span: Span,
+        reference: &RSReference,
+        union: &RSType,
+    ) {
+        let rust_type = match union {
+            RSType::Enum(RSEnum { option, variants }) => {
+                let names: Vec<String> = variants.iter().map(RSType::name).collect();
+                match option {
+                    true => format!("Option<{}>", names.join(" | ")),
+                    false => names.join(" | "),
+                }
+            }
+            union => union.name(),
+        };
+        self.warnings.borrow_mut().insert(
+            (
+                module_path.to_path_buf(),
+                span.start,
+                reference.name().to_string(),
+            ),
+            ConversionWarning {
+                module_path: module_path.to_path_buf(),
+                span,
+                construct: "TSTypeReference".to_string(),
+                fallback: Fallback::Substituted(rust_type),
+            },
+        );
+    }
+
+    /// Expands a template literal type with resolved types to an enum of its strings,
+    /// e.g. `${"a" | "b"}-${"x" | "y"}` to `"a-x" | "a-y" | "b-x" | "b-y"`. Keeps the template,
+    /// with the finite types replaced by their strings, if it has too many expansions or
+    /// one of its types is not a union of literals, e.g. `data-${string}`.
+    fn template_literal_type(&self, quasis: &[String], types: Vec<RSType>, depth: usize) -> RSType {
+        let literals: Vec<Option<Vec<String>>> = types
+            .iter()
+            .map(|rs_type| self.literal_strings(rs_type, depth))
+            .collect();
+
+        let expansions = literals.iter().try_fold(1usize, |count, strings| {
+            count
+                .checked_mul(strings.as_ref()?.len())
+                .filter(|&count| count <= MAX_TEMPLATE_EXPANSIONS)
+        });
+        if expansions.is_some() {
+            let mut expanded = vec![quasis[0].clone()];
+            for (strings, quasi) in literals.iter().flatten().zip(&quasis[1..]) {
+                expanded = expanded
+                    .iter()
+                    .flat_map(|prefix| {
+                        strings
+                            .iter()
+                            .map(move |string| format!("{}{}{}", prefix, string, quasi))
+                    })
+                    .collect();
+            }
+            return make_union_type(
+                expanded
+                    .into_iter()
+                    .map(|string| RSType::EnumVariant(RSEnumVariant::StringLiteral(string)))
+                    .collect(),
+            );
+        }
+
+        let types = types
+            .into_iter()
+            .zip(literals)
+            .map(|(rs_type, strings)| match strings {
+                Some(strings) => make_union_type(
+                    strings
+                        .into_iter()
+                        .map(|string| RSType::EnumVariant(RSEnumVariant::StringLiteral(string)))
+                        .collect(),
+                ),
+                None => rs_type,
+            })
+            .collect();
+        RSType::TemplateLiteral(RSTemplateLiteral {
+            quasis: quasis.to_vec(),
+            types,
+        })
+    }
+
+    /// Returns the strings of a resolved union of literals in a template literal type,
+    /// or None if it is not a union of literals.
+    fn literal_strings(&self, rs_type: &RSType, depth: usize) -> Option<Vec<String>> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        match rs_type {
+            RSType::EnumVariant(RSEnumVariant::StringLiteral(literal))
+            | RSType::EnumVariant(RSEnumVariant::NumericLiteral(literal)) => {
+                Some(vec![literal.clone()])
+            }
+            RSType::EnumVariant(RSEnumVariant::BooleanLiteral(literal)) => {
+                Some(vec![literal.to_string()])
+            }
+            RSType::Primitive(RSPrimitive::Bool) => {
+                Some(vec!["false".to_string(), "true".to_string()])
+            }
+            RSType::EnumVariant(RSEnumVariant::RSType(inner)) => {
+                self.literal_strings(inner, depth + 1)
+            }
+            RSType::Enum(RSEnum {
+                option: false,
+                variants,
+            }) => variants
+                .iter()
+                .map(|variant| self.literal_strings(variant, depth + 1))
+                .collect::<Option<Vec<_>>>()
+                .map(|strings| strings.concat()),
+            RSType::Reference(RSReference::Resolved { name, module_path }) => {
+                let declared_type = self.declared_type(module_path, name, depth)?;
+                self.literal_strings(&declared_type, depth + 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the unresolved branch of a conditional type selected by the check type,
+    /// with the types inferred with `infer` substituted.
+    fn conditional_branch(
+        &self,
+        conditional: &RSConditionalType,
+        check_type: &RSType,
+        extends_type: &RSType,
+        depth: usize,
+    ) -> Option<RSType> {
+        let mut inferred: Vec<(String, RSType)> = Vec::new();
+        match self.extends(check_type, extends_type, &mut inferred, depth)? {
+            true => Some(inferred.iter().fold(
+                *conditional.true_type.clone(),
+                |rs_type, (name, inferred)| rs_type.substitute(name, inferred),
+            )),
+            false => Some(*conditional.false_type.clone()),
+        }
+    }
+
+    /// Returns the members a distributive conditional type is evaluated for:
+    /// those of a union, also through a type alias, or else the type itself.
+    fn union_members(&self, rs_type: &RSType, depth: usize) -> Vec<RSType> {
+        match rs_type {
+            // `never` is the empty union.
+            RSType::Never => Vec::new(),
+            RSType::Enum(RSEnum { option, variants }) => variants
+                .iter()
+                .cloned()
+                .chain(option.then_some(RSType::NullOrUndefined))
+                .collect(),
+            RSType::Option(inner) => vec![*inner.clone(), RSType::NullOrUndefined],
+            RSType::Reference(RSReference::Resolved { name, module_path })
+                if depth <= MAX_RESOLUTION_DEPTH =>
+            {
+                match self.declared_type(module_path, name, depth) {
+                    Some(declared_type @ (RSType::Enum(_) | RSType::Option(_))) => {
+                        self.union_members(&declared_type, depth + 1)
+                    }
+                    _ => vec![rs_type.clone()],
+                }
+            }
+            _ => vec![rs_type.clone()],
+        }
+    }
+
+    /// Returns whether the resolved type `source` is assignable to `target`, binding the types
+    /// inferred with `infer` in `target`. Returns None if it cannot be decided, e.g. for
+    /// unresolved references.
+    fn extends(
+        &self,
+        source: &RSType,
+        target: &RSType,
+        inferred: &mut Vec<(String, RSType)>,
+        depth: usize,
+    ) -> Option<bool> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        if source == target {
+            return Some(true);
+        }
+        let mut all =
+            |sources: &[RSType], target: &RSType, inferred: &mut Vec<(String, RSType)>| {
+                sources.iter().try_fold(true, |all, source| {
+                    Some(all && self.extends(source, target, inferred, depth + 1)?)
+                })
+            };
+        match (source, target) {
+            // e.g. a type parameter of a generic type alias that is not substituted.
+            (
+                RSType::Reference(RSReference::Unresolved { .. } | RSReference::Imported { .. }),
+                _,
+            ) => None,
+            (_, RSType::Infer(name)) => {
+                inferred.push((name.clone(), source.clone()));
+                Some(true)
+            }
+            // `any` and `unknown`, and `never`.
+            (_, RSType::JSONValue) | (RSType::Never, _) => Some(true),
+            (RSType::EnumVariant(RSEnumVariant::NullLiteral), _) => {
+                self.extends(&RSType::NullOrUndefined, target, inferred, depth + 1)
+            }
+            (_, RSType::EnumVariant(RSEnumVariant::NullLiteral)) => {
+                self.extends(source, &RSType::NullOrUndefined, inferred, depth + 1)
+            }
+            (RSType::EnumVariant(RSEnumVariant::RSType(inner)), _) => {
+                self.extends(inner, target, inferred, depth + 1)
+            }
+            (_, RSType::EnumVariant(RSEnumVariant::RSType(inner))) => {
+                self.extends(source, inner, inferred, depth + 1)
+            }
+            (RSType::Reference(RSReference::Resolved { name, module_path }), _) => {
+                let declared_type = self.declared_type(module_path, name, depth)?;
+                self.extends(&declared_type, target, inferred, depth + 1)
+            }
+            (_, RSType::Reference(RSReference::Resolved { name, module_path })) => {
+                let declared_type = self.declared_type(module_path, name, depth)?;
+                self.extends(source, &declared_type, inferred, depth + 1)
+            }
+            // A union extends a type if all its members do.
+            (RSType::Enum(_) | RSType::Option(_), _) => {
+                all(&self.union_members(source, depth), target, inferred)
+            }
+            // A type extends a union if it extends one of its members.
+            (_, RSType::Enum(_) | RSType::Option(_)) => {
+                let mut extends = Some(false);
+                for member in self.union_members(target, depth) {
+                    match self.extends(source, &member, inferred, depth + 1) {
+                        Some(true) => return Some(true),
+                        Some(false) => {}
+                        None => extends = None,
+                    }
+                }
+                extends
+            }
+            (_, RSType::Never) => Some(false),
+            // `object` accepts any non-primitive value.
+            (
+                RSType::Struct(_)
+                | RSType::Vec(_)
+                | RSType::Tuple(_)
+                | RSType::Promise(_)
+                | RSType::HashMap(_, _)
+                | RSType::Flatten(_),
+                RSType::Object,
+            ) => Some(true),
+            (source, RSType::Object)
+                if is_concrete(source)
+                    || matches!(source, RSType::Unit | RSType::TemplateLiteral(_)) =>
+            {
+                Some(false)
+            }
+            // `object` only extends object types without required fields.
+            (RSType::Object, RSType::Struct(target)) => Some(
+                target
+                    .fields
+                    .values()
+                    .all(|field_type| matches!(field_type, RSType::Option(_))),
+            ),
+            (RSType::Object, target) if is_concrete(target) => Some(false),
+            // `void` only accepts `undefined`.
+            (RSType::NullOrUndefined, RSType::Unit) => Some(true),
+            (RSType::Unit, target) if is_concrete(target) => Some(false),
+            (source, RSType::Unit) if is_concrete(source) => Some(false),
+            // A branded type extends its underlying type, but not the other way around.
+            (RSType::Newtype(source_brand, source), RSType::Newtype(target_brand, target)) => {
+                match source_brand == target_brand {
+                    true => self.extends(source, target, inferred, depth + 1),
+                    false => Some(false),
+                }
+            }
+            (RSType::Newtype(_, source), _) => self.extends(source, target, inferred, depth + 1),
+            (_, RSType::Newtype(_, _)) if is_concrete(source) => Some(false),
+            (
+                RSType::EnumVariant(RSEnumVariant::StringLiteral(_)),
+                RSType::Primitive(RSPrimitive::String),
+            )
+            | (
+                RSType::EnumVariant(RSEnumVariant::NumericLiteral(_)),
+                RSType::Primitive(RSPrimitive::F64 | RSPrimitive::I32 | RSPrimitive::I64),
+            )
+            // The numbers inferred from JSON modules are all `number`.
+            | (
+                RSType::Primitive(RSPrimitive::F64 | RSPrimitive::I32 | RSPrimitive::I64),
+                RSType::Primitive(RSPrimitive::F64 | RSPrimitive::I32 | RSPrimitive::I64),
+            )
+            | (
+                RSType::EnumVariant(RSEnumVariant::BooleanLiteral(_)),
+                RSType::Primitive(RSPrimitive::Bool),
+            ) => Some(true),
+            (
+                RSType::EnumVariant(RSEnumVariant::StringLiteral(literal)),
+                RSType::TemplateLiteral(template),
+            ) => Some(template.matches(literal)),
+            (RSType::TemplateLiteral(_), RSType::Primitive(RSPrimitive::String)) => Some(true),
+            (RSType::Vec(source), RSType::Vec(target))
+            | (RSType::Promise(source), RSType::Promise(target))
+            | (RSType::HashMap(_, source), RSType::HashMap(_, target)) => {
+                self.extends(source, target, inferred, depth + 1)
+            }
+            (RSType::Tuple(sources), RSType::Vec(target)) => all(sources, target, inferred),
+            (RSType::Tuple(sources), RSType::Tuple(targets)) if sources.len() == targets.len() => {
+                sources
+                    .iter()
+                    .zip(targets)
+                    .try_fold(true, |all, (source, target)| {
+                        Some(all && self.extends(source, target, inferred, depth + 1)?)
+                    })
+            }
+            // A struct extends another if it has all its required fields.
+            (RSType::Struct(source), RSType::Struct(target)) => {
+                target.fields.iter().try_fold(true, |all, (name, target)| {
+                    match source.fields.get(name) {
+                        Some(source) => {
+                            Some(all && self.extends(source, target, inferred, depth + 1)?)
+                        }
+                        None => Some(all && matches!(target, RSType::Option(_))),
+                    }
+                })
+            }
+            (source, target) if is_concrete(source) && is_concrete(target) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the value referenced by `typeof`, with its references resolved
+    /// from the module declaring the value.
+    fn resolve_type_query(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        reference: &RSReference,
+        depth: usize,
+    ) -> Option<RSType> {
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+        // Types and values with the same name are declared in the same module.
+        let (value_module_path, name) =
+            match self.resolve_reference_name(module_path, scope, reference)? {
+                Resolution::Value { module_path, name } => (module_path, name),
+                Resolution::Declaration { module_path, name } => (module_path, name),
+                _ => return None,
+            };
+        let value_type = self.modules.get(&value_module_path)?.values.get(&name)?;
+
+        let value_scope: Vec<&str> = name.split('.').collect();
+        let value_scope = &value_scope[..value_scope.len() - 1];
+        Some(resolve_type(value_type, &mut |leaf| {
+            self.resolve_leaf(&value_module_path, value_scope, leaf, depth + 1)
+        }))
+    }
+
+    /// Resolves the name of an unresolved or imported reference.
+    fn resolve_reference_name(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        reference: &RSReference,
+    ) -> Option<Resolution> {
+        match reference {
+            RSReference::Unresolved {
+                name,
+                module_specifier: None,
+            } => {
+                let segments: Vec<&str> = name.split('.').collect();
+                self.resolve_name(module_path, scope, &segments, 0)
+            }
+            // `import("./foo").Bar` is looked up in the exports of the imported module.
+            RSReference::Imported { name, module_path } => {
+                let segments: Vec<&str> = name.split('.').collect();
+                self.resolve_export(module_path, &segments, 0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolves a dotted name (e.g. `axe.Result`) as seen from the namespace `scope` of a module:
+    /// first in the enclosing namespaces, then in the imports, then in the globals.
+    pub fn resolve_name(
+        &self,
+        module_path: &Path,
+        scope: &[&str],
+        segments: &[&str],
+        depth: usize,
+    ) -> Option<Resolution> {
+        let (first, rest) = segments.split_first()?;
+        let module = self.modules.get(module_path)?;
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+
+        for i in (0..=scope.len()).rev() {
+            let qualified_name = [&scope[..i], &[*first]].concat().join(".");
+
+            if let Some(resolution) = self.resolve_local(module_path, &qualified_name, rest) {
+                return Some(resolution);
+            }
+
+            if let Some(entity) = module.aliases.get(&qualified_name) {
+                let segments: Vec<&str> = entity.split('.').chain(rest.iter().copied()).collect();
+                return self.resolve_name(module_path, &scope[..i], &segments, depth + 1);
+            }
+        }
+
+        if let Some(mapping) = module.imports.get(*first) {
+            return self.resolve_mapping(mapping, rest, depth + 1);
+        }
+
+        if let Some(global_module_path) = self.namespace_exports.get(first) {
+            return self.resolve_export(global_module_path, rest, depth + 1);
+        }
+
+        self.resolve_global(scope, segments)
+    }
+
+    /// Resolves a dotted name declared in the global scope,
+    /// from the innermost enclosing namespace outwards.
+    fn resolve_global(&self, scope: &[&str], segments: &[&str]) -> Option<Resolution> {
+        for i in (0..=scope.len()).rev() {
+            let name = [&scope[..i], segments].concat().join(".");
+
+            if self.globals.contains(name.as_str()) {
+                return Some(Resolution::Declaration {
+                    module_path: PathBuf::from(GLOBAL_SCOPE),
+                    name,
+                });
+            }
+
+            let prefix = format!("{}.", name);
+            if self
+                .globals
+                .iter()
+                .any(|global| global.starts_with(&prefix))
+            {
+                return Some(Resolution::Namespace {
+                    module_path: PathBuf::from(GLOBAL_SCOPE),
+                    name: Some(name),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Resolves a dotted name exported from a module.
+    pub fn resolve_export(
+        &self,
+        module_path: &Path,
+        segments: &[&str],
+        depth: usize,
+    ) -> Option<Resolution> {
+        let module = self.modules.get(module_path)?;
+        if depth > MAX_RESOLUTION_DEPTH {
+            return None;
+        }
+
+        // `export = entity`: the exports are the members of the entity,
+        // which is also the default export with `esModuleInterop`.
+        if let Some(entity) = &module.export_assignment {
+            let segments = match segments.first() {
+                Some(&"default") => &segments[1..],
+                _ => segments,
+            };
+            let segments: Vec<&str> = entity.split('.').chain(segments.iter().copied()).collect();
+            return self.resolve_name(module_path, &[], &segments, depth + 1);
+        }
+
+        let Some((first, rest)) = segments.split_first() else {
+            return Some(Resolution::Namespace {
+                module_path: module_path.to_path_buf(),
+                name: None,
+            });
+        };
+
+        if let Some(mapping) = module.exports.get(*first) {
+            if mapping.original_module.is_some() || mapping.module_specifier.is_some() {
+                return self.resolve_mapping(mapping, rest, depth + 1);
+            }
+            let segments: Vec<&str> = [mapping.local_name.as_str()]
+                .into_iter()
+                .chain(rest.iter().copied())
+                .collect();
+            return self.resolve_name(module_path, &[], &segments, depth + 1);
+        }
+
+        // Declaration files implicitly export their top-level declarations.
+        if let Some(resolution) = self.resolve_local(module_path, first, rest) {
+            return Some(resolution);
+        }
+
+        // Names from star exports that have not been expanded yet,
+        // unless they are ambiguous.
+        if *first != "default" {
+            let mut resolutions: Vec<Resolution> = Vec::new();
+            for star_export in &module.star_exports {
+                let Some(original_module) = &star_export.original_module else {
+                    continue;
+                };
+                if let Some(resolution) = self.resolve_export(original_module, segments, depth + 1)
+                {
+                    if !resolutions.contains(&resolution) {
+                        resolutions.push(resolution);
+                    }
+                }
+            }
+            match resolutions.len() {
+                0 => {}
+                1 => return resolutions.pop(),
+                _ => return None,
+            }
+        }
+
+        // Names from a single unresolvable `export * from "source"` stay external.
+        let mut unresolved_star_exports = module
+            .star_exports
+            .iter()
+            .filter(|star_export| star_export.original_module.is_none());
+        match (
+            unresolved_star_exports.next(),
+            unresolved_star_exports.next(),
+        ) {
+            (Some(star_export), None) => Some(Resolution::External {
+                name: segments.join("."),
+                module_specifier: star_export.module_specifier.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the names exported from a module, including those of its star exports.
+    pub fn exported_names(
+        &self,
+        module_path: &Path,
+        visiting: &mut HashSet<PathBuf>,
+    ) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        let Some(module) = self.modules.get(module_path) else {
+            return names;
+        };
+        if !visiting.insert(module_path.to_path_buf()) {
+            return names;
+        }
+
+        match &module.export_assignment {
+            // `export = entity`: the members of the entity.
+            Some(entity) => {
+                let prefix = format!("{}.", entity);
+                names.extend(module.types.keys().filter_map(|key| {
+                    let member = key.strip_prefix(&prefix)?;
+                    Some(member.split('.').next().unwrap_or(member).to_string())
+                }));
+            }
+            None => {
+                names.extend(module.exports.keys().cloned());
+                names.extend(module.top_level_names().map(String::from));
+                for star_export in &module.star_exports {
+                    if let Some(original_module) = &star_export.original_module {
+                        let star_names = self.exported_names(original_module, visiting);
+                        names.extend(star_names.into_iter().filter(|name| name != "default"));
+                    }
+                }
+            }
+        }
+
+        visiting.remove(module_path);
+        names
+    }
+
+    /// Resolves a dotted name through an import or re-export.
+    fn resolve_mapping(
+        &self,
+        mapping: &TypeMapping,
+        rest: &[&str],
+        depth: usize,
+    ) -> Option<Resolution> {
+        let original_name = match &mapping.original_name {
+            OriginalName::Named(name) => Some(name.as_str()),
+            OriginalName::Default => Some("default"),
+            OriginalName::Namespace => None,
+        };
+        let segments: Vec<&str> = original_name
+            .into_iter()
+            .chain(rest.iter().copied())
+            .collect();
+
+        match (&mapping.original_module, &mapping.module_specifier) {
+            (Some(original_module), _) => self.resolve_export(original_module, &segments, depth),
+            (None, Some(module_specifier)) => Some(Resolution::External {
+                name: segments.join("."),
+                module_specifier: module_specifier.clone(),
+            }),
+            (None, None) => None,
+        }
+    }
+
+    /// Resolves a dotted name declared in a module, e.g. the namespace `axe` and the rest `Result`.
+    fn resolve_local(
+        &self,
+        module_path: &Path,
+        qualified_name: &str,
+        rest: &[&str],
+    ) -> Option<Resolution> {
+        let module = self.modules.get(module_path)?;
+        let name = [qualified_name]
+            .into_iter()
+            .chain(rest.iter().copied())
+            .collect::<Vec<_>>()
+            .join(".");
+
+        if module.types.contains_key(&name) {
+            return Some(Resolution::Declaration {
+                module_path: module_path.to_path_buf(),
+                name,
+            });
+        }
+
+        if module.has_namespace(&name) {
+            return Some(Resolution::Namespace {
+                module_path: module_path.to_path_buf(),
+                name: Some(name),
+            });
+        }
+
+        if module.values.contains_key(&name) {
+            return Some(Resolution::Value {
+                module_path: module_path.to_path_buf(),
+                name,
+            });
+        }
+
+        None
+    }
+}
+
+/// Rebuilds a type with its references, `typeof` queries and `keyof` operators (the leaves)
+/// replaced by `resolve`.
+fn resolve_type(rs_type: &RSType, resolve: &mut impl FnMut(&RSType) -> RSType) -> RSType {
     match rs_type {
-        RSType::Reference(reference) => RSType::Reference(reference.clone()),
+        RSType::Reference(_)
+        | RSType::TypeQuery(_, _)
+        | RSType::KeyOf(_, _)
+        | RSType::IndexedAccess(_, _, _)
+        | RSType::Mapped(_)
+        | RSType::Instantiation(_, _, _)
+        | RSType::Conditional(_)
+        | RSType::TemplateLiteral(_)
+        | RSType::Intersection(_, _) => resolve(rs_type),
+        RSType::Flatten(members) => RSType::Flatten(
+            members
+                .iter()
+                .map(|member| resolve_type(member, resolve))
+                .collect(),
+        ),
+        RSType::Tuple(elements) => RSType::Tuple(
+            elements
+                .iter()
+                .map(|element| resolve_type(element, resolve))
+                .collect(),
+        ),
         // Recursively resolve contained types for Vec and Option
-        RSType::Vec(inner) => RSType::Vec(Box::new(resolve_type(inner, type_map, references))),
-        RSType::Option(inner) => {
-            RSType::Option(Box::new(resolve_type(inner, type_map, references)))
+        RSType::Vec(inner) => RSType::Vec(Box::new(resolve_type(inner, resolve))),
+        RSType::Promise(inner) => RSType::Promise(Box::new(resolve_type(inner, resolve))),
+        RSType::Option(inner) => RSType::Option(Box::new(resolve_type(inner, resolve))),
+        RSType::Newtype(brand, inner) => {
+            RSType::Newtype(brand.clone(), Box::new(resolve_type(inner, resolve)))
         }
+        RSType::Brand(inner, span) => RSType::Brand(Box::new(resolve_type(inner, resolve)), *span),
+        RSType::HashMap(key, value) => RSType::HashMap(
+            Box::new(resolve_type(key, resolve)),
+            Box::new(resolve_type(value, resolve)),
+        ),
         RSType::Enum(RSEnum { option, variants }) => {
             let variants = variants
                 .iter()
-                .map(|variant| resolve_type(variant, type_map, references))
+                .map(|variant| resolve_type(variant, resolve))
                 .collect();
             RSType::Enum(RSEnum {
                 option: *option,
@@ -54,17 +1885,977 @@ fn resolve_type(
             let fields = fields
                 .iter()
                 .map(|(field_name, field_type)| {
-                    (
-                        field_name.clone(),
-                        resolve_type(field_type, type_map, references),
-                    )
+                    (field_name.clone(), resolve_type(field_type, resolve))
                 })
                 .collect();
             RSType::Struct(RSStruct { fields })
         }
-        RSType::EnumVariant(RSEnumVariant::RSType(inner)) => {
-            resolve_type(inner, type_map, references)
-        }
+        RSType::EnumVariant(RSEnumVariant::RSType(inner)) => resolve_type(inner, resolve),
         _ => rs_type.clone(),
     }
 }
+
+/// Collects the references and `typeof` queries of a resolved type that could not be resolved,
+/// including those of the operators that could not be evaluated.
+fn collect_unresolved(rs_type: &RSType, unresolved: &mut HashSet<RSReference>) {
+    match rs_type {
+        RSType::Reference(RSReference::Resolved { .. }) => {}
+        RSType::Reference(reference) | RSType::TypeQuery(reference, _) => {
+            unresolved.insert(reference.clone());
+        }
+        RSType::Vec(inner)
+        | RSType::Promise(inner)
+        | RSType::Option(inner)
+        | RSType::KeyOf(inner, _)
+        | RSType::Newtype(_, inner)
+        | RSType::Brand(inner, _)
+        | RSType::EnumVariant(RSEnumVariant::RSType(inner)) => {
+            collect_unresolved(inner, unresolved)
+        }
+        RSType::HashMap(first, second) | RSType::IndexedAccess(first, second, _) => {
+            collect_unresolved(first, unresolved);
+            collect_unresolved(second, unresolved);
+        }
+        // The value of an unevaluated mapped type still refers to its key type parameter,
+        // and the branches of a conditional type to its type parameters.
+        RSType::Mapped(mapped) => collect_unresolved(&mapped.keys, unresolved),
+        RSType::Conditional(conditional) => {
+            collect_unresolved(&conditional.check_type, unresolved);
+            collect_unresolved(&conditional.extends_type, unresolved);
+        }
+        RSType::Enum(RSEnum { variants, .. })
+        | RSType::TemplateLiteral(RSTemplateLiteral {
+            types: variants, ..
+        })
+        | RSType::Intersection(variants, _)
+        | RSType::Flatten(variants)
+        | RSType::Tuple(variants) => {
+            for variant in variants {
+                collect_unresolved(variant, unresolved);
+            }
+        }
+        RSType::Struct(RSStruct { fields }) => {
+            for field_type in fields.values() {
+                collect_unresolved(field_type, unresolved);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collects the type operators of a resolved type that could not be evaluated.
+fn collect_unevaluated<'t>(rs_type: &'t RSType, leaves: &mut Vec<&'t RSType>) {
+    match rs_type {
+        _ if unevaluated_construct(rs_type).is_some() => leaves.push(rs_type),
+        RSType::Vec(inner)
+        | RSType::Promise(inner)
+        | RSType::Option(inner)
+        | RSType::Newtype(_, inner)
+        | RSType::Brand(inner, _)
+        | RSType::EnumVariant(RSEnumVariant::RSType(inner)) => collect_unevaluated(inner, leaves),
+        RSType::HashMap(key, value) => {
+            collect_unevaluated(key, leaves);
+            collect_unevaluated(value, leaves);
+        }
+        RSType::Enum(RSEnum { variants, .. })
+        | RSType::TemplateLiteral(RSTemplateLiteral {
+            types: variants, ..
+        })
+        | RSType::Flatten(variants)
+        | RSType::Tuple(variants) => {
+            for variant in variants {
+                collect_unevaluated(variant, leaves);
+            }
+        }
+        RSType::Struct(RSStruct { fields }) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            for (_, field_type) in fields {
+                collect_unevaluated(field_type, leaves);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Removes the brand fields left in the structs of a resolved type, which are not part of
+/// a branded type, and collects their names and spans.
+fn drop_brand_fields(rs_type: &mut RSType, dropped: &mut Vec<(String, Span)>) {
+    match rs_type {
+        RSType::Struct(RSStruct { fields }) => {
+            fields.retain(|field_name, field_type| match field_type {
+                RSType::Brand(_, span) => {
+                    dropped.push((field_name.clone(), *span));
+                    false
+                }
+                _ => true,
+            });
+            for field_type in fields.values_mut() {
+                drop_brand_fields(field_type, dropped);
+            }
+        }
+        RSType::Vec(inner)
+        | RSType::Promise(inner)
+        | RSType::Option(inner)
+        | RSType::Newtype(_, inner)
+        | RSType::EnumVariant(RSEnumVariant::RSType(inner)) => drop_brand_fields(inner, dropped),
+        RSType::HashMap(key, value) => {
+            drop_brand_fields(key, dropped);
+            drop_brand_fields(value, dropped);
+        }
+        RSType::Enum(RSEnum { variants, .. })
+        | RSType::Intersection(variants, _)
+        | RSType::Flatten(variants)
+        | RSType::Tuple(variants) => {
+            for variant in variants {
+                drop_brand_fields(variant, dropped);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the TypeScript construct and span of a type operator left unevaluated.
+fn unevaluated_construct(rs_type: &RSType) -> Option<(&'static str, Span)> {
+    match rs_type {
+        RSType::TypeQuery(_, span) => Some(("TSTypeQuery", *span)),
+        RSType::KeyOf(_, span) => Some(("TSTypeOperator", *span)),
+        RSType::IndexedAccess(_, _, span) => Some(("TSIndexedAccessType", *span)),
+        RSType::Mapped(mapped) => Some(("TSMappedType", mapped.span)),
+        RSType::Conditional(conditional) => Some(("TSConditionalType", conditional.span)),
+        RSType::Intersection(_, span) => Some(("TSIntersectionType", *span)),
+        _ => None,
+    }
+}
+
+/// Returns the union of the types, with nested unions flattened and `never` left out,
+/// or the type itself if there is only one.
+fn make_union_type(types: Vec<RSType>) -> RSType {
+    let mut option = false;
+    let mut variants: Vec<RSType> = Vec::new();
+    for rs_type in types {
+        let members = match rs_type {
+            RSType::Enum(RSEnum {
+                option: nullable,
+                variants,
+            }) => {
+                option |= nullable;
+                variants
+            }
+            rs_type => vec![rs_type],
+        };
+        for member in members {
+            match member {
+                RSType::Never => {}
+                RSType::NullOrUndefined | RSType::EnumVariant(RSEnumVariant::NullLiteral) => {
+                    option = true
+                }
+                member if !variants.contains(&member) => variants.push(member),
+                _ => {}
+            }
+        }
+    }
+    match (option, variants.len()) {
+        (false, 0) => RSType::Never,
+        (true, 0) => RSType::NullOrUndefined,
+        (false, 1) => variants.remove(0),
+        _ => RSType::Enum(RSEnum { option, variants }),
+    }
+}
+
+/// Returns true for the types whose assignability to each other is known.
+fn is_concrete(rs_type: &RSType) -> bool {
+    matches!(
+        rs_type,
+        RSType::Primitive(_)
+            | RSType::EnumVariant(
+                RSEnumVariant::StringLiteral(_)
+                    | RSEnumVariant::NumericLiteral(_)
+                    | RSEnumVariant::BooleanLiteral(_)
+            )
+            | RSType::NullOrUndefined
+            | RSType::Vec(_)
+            | RSType::Tuple(_)
+            | RSType::Promise(_)
+            | RSType::HashMap(_, _)
+            | RSType::Struct(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{fixtures::*, options::TypeScriptOptions};
+    use super::*;
+
+    #[test]
+    fn export_assignments_export_the_namespace_members() {
+        let fixture = Fixture::new(
+            "export_assignment",
+            &[
+                (
+                    "lib.d.ts",
+                    r#"
+                    declare namespace lib {
+                        interface Result { id: string }
+                        type Impact = "minor" | "serious";
+                    }
+                    export = lib;
+                    export as namespace libGlobal;
+                    "#,
+                ),
+                (
+                    "index.ts",
+                    r#"
+                    import { Result } from "./lib";
+                    import lib = require("./lib");
+                    export interface Report { result: Result; impact: lib.Impact }
+                    "#,
+                ),
+                (
+                    "globals.d.ts",
+                    "interface GlobalReport { result: libGlobal.Result }\n",
+                ),
+            ],
+        );
+        let (builder, result) = fixture.visit(&["index.ts", "globals.d.ts"], Default::default());
+        result.unwrap();
+        let lib = fixture.path("lib.d.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["Report"],
+            object(vec![
+                ("result", resolved("lib.Result", &lib)),
+                ("impact", resolved("lib.Impact", &lib)),
+            ])
+        );
+        // `libGlobal` is the global name of the UMD module.
+        assert_eq!(
+            builder.modules[&fixture.path("globals.d.ts")].globals["GlobalReport"],
+            object(vec![("result", resolved("lib.Result", &lib))])
+        );
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn star_exports_are_followed_and_ambiguous_names_left_out() {
+        let fixture = Fixture::new(
+            "star_exports",
+            &[
+                (
+                    "a.ts",
+                    "export interface User { id: string }\nexport interface Shared { a: string }\n",
+                ),
+                (
+                    "b.ts",
+                    "export interface Order { id: number }\nexport interface Shared { b: string }\n",
+                ),
+                (
+                    "index.ts",
+                    "export * from './a';\nexport * from './b';\nexport * as models from './a';\n",
+                ),
+                (
+                    "shadowed.ts",
+                    "export * from './a';\nexport interface Shared { c: boolean }\n",
+                ),
+                (
+                    "app.ts",
+                    r#"
+                    import type { User, Order, Shared, models } from "./index";
+                    import type { Shared as Local } from "./shadowed";
+                    export interface App {
+                        user: User;
+                        order: Order;
+                        nested: models.User;
+                        shared: Shared;
+                        local: Local;
+                    }
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("app.ts");
+        let (a, b) = (fixture.path("a.ts"), fixture.path("b.ts"));
+        assert_eq!(
+            types(&builder, &fixture.path("app.ts"))["App"],
+            object(vec![
+                ("user", resolved("User", &a)),
+                ("order", resolved("Order", &b)),
+                ("nested", resolved("User", &a)),
+                (
+                    "shared",
+                    RSType::Reference(RSReference::Unresolved {
+                        name: "Shared".to_string(),
+                        module_specifier: None,
+                    })
+                ),
+                // Local declarations shadow star exports.
+                ("local", resolved("Shared", &fixture.path("shadowed.ts"))),
+            ])
+        );
+        // Both star exports of the ambiguous name are reported.
+        let ambiguous = "`ExportAllDeclaration` dropped: `Shared`, \
+            which is exported by more than one `export *` declaration";
+        assert_eq!(warnings(&builder), [ambiguous, ambiguous]);
+        assert!(builder
+            .warnings()
+            .iter()
+            .all(|warning| warning.module_path == fixture.path("index.ts")));
+    }
+
+    #[test]
+    fn default_exports_resolve_to_their_declarations() {
+        let fixture = Fixture::new(
+            "default_exports",
+            &[
+                ("user.ts", "export default interface User { id: string }\n"),
+                (
+                    "order.ts",
+                    "interface Order { total: number }\nexport default Order;\n",
+                ),
+                (
+                    "index.ts",
+                    r#"
+                    import Customer from "./user";
+                    import Order from "./order";
+                    export interface Cart { customer: Customer; order: Order }
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("index.ts");
+        // The references are named after the declarations, not the local import names.
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["Cart"],
+            object(vec![
+                ("customer", resolved("User", &fixture.path("user.ts"))),
+                ("order", resolved("Order", &fixture.path("order.ts"))),
+            ])
+        );
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn qualified_names_resolve_through_namespaces() {
+        let fixture = Fixture::new(
+            "qualified_names",
+            &[
+                (
+                    "models.ts",
+                    r#"
+                    export namespace NS {
+                        export namespace Inner { export interface Type { id: string } }
+                        export type Alias = Inner.Type;
+                    }
+                    export interface Result { ok: boolean }
+                    "#,
+                ),
+                (
+                    "index.ts",
+                    r#"
+                    import * as models from "./models";
+                    export interface Page { result: models.Result; inner: models.NS.Inner.Type; alias: models.NS.Alias }
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("index.ts");
+        let models = fixture.path("models.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["Page"],
+            object(vec![
+                ("result", resolved("Result", &models)),
+                ("inner", resolved("NS.Inner.Type", &models)),
+                ("alias", resolved("NS.Alias", &models)),
+            ])
+        );
+        // References in a namespace are resolved from the namespace.
+        assert_eq!(
+            types(&builder, &models)["NS.Alias"],
+            resolved("NS.Inner.Type", &models)
+        );
+        assert_eq!(resolved("NS.Inner.Type", &models).name(), "NS::Inner::Type");
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn import_types_resolve_to_their_modules() {
+        let fixture = Fixture::new(
+            "import_types",
+            &[
+                (
+                    "user.d.ts",
+                    "export interface User { id: string }\nexport type Page<T> = { items: T[] };\n",
+                ),
+                (
+                    "index.d.ts",
+                    r#"
+                    export interface Session {
+                        user: import("./user").User;
+                        users: import("./user").Page<import("./user").User>;
+                        module: typeof import("./user");
+                    }
+                    "#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("index.d.ts");
+        let user = fixture.path("user.d.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("index.d.ts"))["Session"],
+            object(vec![
+                ("user", resolved("User", &user)),
+                (
+                    "users",
+                    object(vec![(
+                        "items",
+                        RSType::Vec(Box::new(resolved("User", &user)))
+                    )])
+                ),
+            ])
+        );
+        // The type of a module is not converted.
+        assert_eq!(
+            warnings(&builder),
+            ["`TSImportType` dropped: field `Session.module`"]
+        );
+    }
+
+    #[test]
+    fn reference_directives_and_global_declarations_are_linked() {
+        let fixture = Fixture::new(
+            "reference_directives",
+            &[
+                ("other.d.ts", "interface Extra { extra: string }\n"),
+                (
+                    "node_modules/@types/node/index.d.ts",
+                    "interface Buffer { length: number }\n",
+                ),
+                (
+                    "polyfills.ts",
+                    "declare global { interface Polyfill { name: string } }\nexport {};\n",
+                ),
+                (
+                    "index.d.ts",
+                    r#"/// <reference path="./other.d.ts" />
+/// <reference types="node" />
+import "./polyfills";
+declare global { interface AppWindow { title: string } }
+export interface Data { extra: Extra; buffer: Buffer; window: AppWindow; polyfill: Polyfill }
+"#,
+                ),
+            ],
+        );
+        let builder = fixture.convert("index.d.ts");
+        let global_scope = Path::new(GLOBAL_SCOPE);
+        assert_eq!(
+            types(&builder, &fixture.path("index.d.ts"))["Data"],
+            object(vec![
+                ("extra", resolved("Extra", global_scope)),
+                ("buffer", resolved("Buffer", global_scope)),
+                ("window", resolved("AppWindow", global_scope)),
+                ("polyfill", resolved("Polyfill", global_scope)),
+            ])
+        );
+        let globals = types(&builder, global_scope);
+        assert_eq!(globals["Buffer"], object(vec![("length", NUMBER)]));
+        assert_eq!(globals["AppWindow"], object(vec![("title", STRING)]));
+        assert!(warnings(&builder).is_empty());
+        assert!(diagnostics(&builder).is_empty());
+    }
+
+    #[test]
+    fn script_declarations_are_merged_into_the_global_scope() {
+        let fixture = Fixture::new(
+            "script_globals",
+            &[
+                (
+                    "a.d.ts",
+                    "interface Theme { color: string }\ntype Mode = 'light';\n",
+                ),
+                (
+                    "b.d.ts",
+                    "interface Theme { size: number }\ntype Mode = 'dark';\n",
+                ),
+                (
+                    "index.ts",
+                    "export interface App { theme: Theme; mode: Mode; color: Theme['color'] }\n",
+                ),
+            ],
+        );
+        let (builder, result) =
+            fixture.visit(&["a.d.ts", "b.d.ts", "index.ts"], Default::default());
+        result.unwrap();
+        let global_scope = Path::new(GLOBAL_SCOPE);
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["App"],
+            object(vec![
+                ("theme", resolved("Theme", global_scope)),
+                ("mode", resolved("Mode", global_scope)),
+                ("color", STRING),
+            ])
+        );
+        // Interfaces are merged, and for other declarations the first one wins.
+        let globals = types(&builder, global_scope);
+        assert_eq!(
+            globals["Theme"],
+            object(vec![("color", STRING), ("size", NUMBER)])
+        );
+        assert_eq!(globals["Mode"], string_literal("light"));
+        assert_eq!(
+            diagnostics(&builder),
+            [format!(
+                "Conflicting declarations of the global type `Mode` in {}",
+                fixture.path("b.d.ts").display()
+            )]
+        );
+        assert!(warnings(&builder).is_empty());
+    }
+
+    #[test]
+    fn keyof_and_typeof_are_evaluated() {
+        let fixture = Fixture::new(
+            "keyof_typeof",
+            &[(
+                "index.ts",
+                r#"
+                export interface User { id: number; name: string }
+                declare const defaults: { retries: number; mode: "fast" };
+                const kinds = { a: "x", b: "y" } as const;
+                export type Key = keyof User;
+                export type Defaults = typeof defaults;
+                export type Kind = keyof typeof kinds;
+                export type Missing = keyof Unknown;
+                "#,
+            )],
+        );
+        let builder = fixture.convert("index.ts");
+        let types = types(&builder, &fixture.path("index.ts"));
+        assert_eq!(
+            types["Key"],
+            union(vec![string_literal("id"), string_literal("name")])
+        );
+        assert_eq!(
+            types["Defaults"],
+            object(vec![("retries", NUMBER), ("mode", string_literal("fast"))])
+        );
+        assert_eq!(
+            types["Kind"],
+            union(vec![string_literal("a"), string_literal("b")])
+        );
+        // `keyof` of an unresolved type is kept, and reported.
+        assert!(matches!(types["Missing"], RSType::KeyOf(_, _)));
+        assert_eq!(
+            warnings(&builder),
+            ["`TSTypeOperator` could not be evaluated"]
+        );
+    }
+
+    #[test]
+    fn self_referencing_declarations_are_resolved_once() {
+        let fixture = Fixture::new(
+            "self_referencing",
+            &[(
+                "index.ts",
+                r#"
+                export interface Node {
+                    a: string;
+                    k1: keyof Node;
+                    k2: keyof Node;
+                    k3: keyof Node;
+                    k4: keyof Node;
+                    value: Node["a"];
+                    parent?: Node;
+                }
+                "#,
+            )],
+        );
+        let started = std::time::Instant::now();
+        let builder = fixture.convert("index.ts");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        let RSType::Struct(node) = &types(&builder, &fixture.path("index.ts"))["Node"] else {
+            panic!("`Node` is a struct");
+        };
+        // The property names are sorted.
+        let keys = union(
+            ["a", "k1", "k2", "k3", "k4", "parent", "value"]
+                .into_iter()
+                .map(string_literal)
+                .collect(),
+        );
+        for field_name in ["k1", "k2", "k3", "k4"] {
+            assert_eq!(node.fields[field_name], keys);
+        }
+        assert_eq!(node.fields["value"], STRING);
+        assert_eq!(
+            node.fields["parent"],
+            option(resolved("Node", &fixture.path("index.ts")))
+        );
+        assert!(warnings(&builder).is_empty());
+    }
+
+    #[test]
+    fn indexed_access_selects_fields_and_elements() {
+        let types = resolve(
+            "indexed",
+            r#"
+            interface User { id: number; name: string; tags: string[] }
+            type Pair = [string, number];
+            export type Id = User["id"];
+            export type Value = User["id" | "name"];
+            export type Tag = User["tags"][number];
+            export type First = Pair[0];
+            export type Second = Pair[1];
+            export type Element = Pair[number];
+            "#,
+        );
+        assert_eq!(types["Id"], NUMBER);
+        assert_eq!(types["Value"], union(vec![NUMBER, STRING]));
+        assert_eq!(types["Tag"], STRING);
+        assert_eq!(types["First"], STRING);
+        assert_eq!(types["Second"], NUMBER);
+        assert_eq!(types["Element"], union(vec![STRING, NUMBER]));
+    }
+
+    #[test]
+    fn mapped_types_are_evaluated_to_structs_and_maps() {
+        let types = resolve(
+            "mapped",
+            r#"
+            interface User { id: number; name: string }
+            export type Flags = { [K in "a" | "b"]: boolean };
+            export type Strings = { [K in keyof User]: string };
+            export type Counts = { [key: string]: number };
+            export type ByName = { [K in string]: User["id"] };
+            "#,
+        );
+        assert_eq!(types["Flags"], object(vec![("a", BOOLEAN), ("b", BOOLEAN)]));
+        assert_eq!(
+            types["Strings"],
+            object(vec![("id", STRING), ("name", STRING)])
+        );
+        assert_eq!(
+            types["Counts"],
+            RSType::HashMap(Box::new(STRING), Box::new(NUMBER))
+        );
+        assert_eq!(
+            types["ByName"],
+            RSType::HashMap(Box::new(STRING), Box::new(NUMBER))
+        );
+    }
+
+    #[test]
+    fn utility_types_are_built_in_and_unknown_generics_reported() {
+        let fixture = Fixture::new(
+            "utility_types",
+            &[(
+                "index.ts",
+                r#"
+                interface User { id: number; name: string; email?: string }
+                export type A = Partial<User>;
+                export type B = Required<User>;
+                export type C = Pick<User, "id" | "email">;
+                export type D = Omit<User, "email">;
+                export type E = Record<"a" | "b", number>;
+                export type F = Record<string, User>;
+                export type G = Set<User>;
+                "#,
+            )],
+        );
+        let builder = fixture.convert("index.ts");
+        let index = fixture.path("index.ts");
+        let types = types(&builder, &index);
+        assert_eq!(
+            types["A"],
+            object(vec![
+                ("id", option(NUMBER)),
+                ("name", option(STRING)),
+                ("email", option(STRING)),
+            ])
+        );
+        assert_eq!(
+            types["B"],
+            object(vec![("id", NUMBER), ("name", STRING), ("email", STRING)])
+        );
+        assert_eq!(
+            types["C"],
+            object(vec![("id", NUMBER), ("email", option(STRING))])
+        );
+        assert_eq!(types["D"], object(vec![("id", NUMBER), ("name", STRING)]));
+        assert_eq!(types["E"], object(vec![("a", NUMBER), ("b", NUMBER)]));
+        assert_eq!(
+            types["F"],
+            RSType::HashMap(Box::new(STRING), Box::new(resolved("User", &index)))
+        );
+        assert_eq!(types["G"], union(vec![resolved("User", &index)]));
+        assert_eq!(
+            warnings(&builder),
+            vec!["`TSTypeReference` converted to `User`"]
+        );
+    }
+
+    #[test]
+    fn conditional_types_select_a_branch() {
+        let types = resolve(
+            "conditional",
+            r#"
+            type IsString<T> = T extends string ? "yes" : "no";
+            type ElementOf<T> = T extends (infer U)[] ? U : never;
+            type Unwrap<T> = T extends Promise<infer U> ? U : T;
+            export type A = IsString<"a">;
+            export type B = IsString<number>;
+            export type C = ElementOf<boolean[]>;
+            export type D = ElementOf<string>;
+            export type E = Unwrap<Promise<string>>;
+            export type F = IsString<"a" | 1>;
+            export type G = void extends undefined ? 1 : 2;
+            "#,
+        );
+        assert_eq!(types["A"], string_literal("yes"));
+        assert_eq!(types["B"], string_literal("no"));
+        assert_eq!(types["C"], BOOLEAN);
+        assert_eq!(types["D"], RSType::Never);
+        assert_eq!(types["E"], STRING);
+        assert_eq!(
+            types["F"],
+            union(vec![string_literal("yes"), string_literal("no")])
+        );
+        assert_eq!(
+            types["G"],
+            RSType::EnumVariant(RSEnumVariant::NumericLiteral("2".to_string()))
+        );
+    }
+
+    #[test]
+    fn object_only_accepts_non_primitive_values() {
+        let fixture = Fixture::new(
+            "object_extends",
+            &[(
+                "index.ts",
+                r#"
+                type IsObj<T> = T extends object ? "obj" : "prim";
+                type DeepPartial<T> = T extends object ? { [K in keyof T]?: DeepPartial<T[K]> } : T;
+                export type A = IsObj<string>;
+                export type B = IsObj<{ a: 1 }>;
+                export type C = IsObj<number[]>;
+                export type D = DeepPartial<{ name: string; size: { width: number } }>;
+                "#,
+            )],
+        );
+        let builder = fixture.convert("index.ts");
+        let types = types(&builder, &fixture.path("index.ts"));
+        assert_eq!(types["A"], string_literal("prim"));
+        assert_eq!(types["B"], string_literal("obj"));
+        assert_eq!(types["C"], string_literal("obj"));
+        assert_eq!(
+            types["D"],
+            object(vec![
+                ("name", option(STRING)),
+                ("size", option(object(vec![("width", option(NUMBER))]))),
+            ])
+        );
+        assert_eq!(
+            warnings(&builder),
+            vec![
+                "`TSObjectKeyword` converted to `serde_json::Value`",
+                "`TSObjectKeyword` converted to `serde_json::Value`",
+            ]
+        );
+    }
+
+    #[test]
+    fn exclude_and_extract_filter_unions() {
+        let types = resolve(
+            "exclude_extract",
+            r#"
+            type Kind = "a" | "b" | "c";
+            export type A = Exclude<Kind, "a">;
+            export type B = Extract<Kind, "a" | "z">;
+            export type C = Exclude<Kind, string>;
+            export type D = NonNullable<string | null>;
+            "#,
+        );
+        assert_eq!(
+            types["A"],
+            union(vec![string_literal("b"), string_literal("c")])
+        );
+        assert_eq!(types["B"], string_literal("a"));
+        assert_eq!(types["C"], RSType::Never);
+        assert_eq!(types["D"], STRING);
+    }
+
+    #[test]
+    fn promises_are_unwrapped_in_fields() {
+        let types = resolve(
+            "promise",
+            r#"
+            type Lazy<T> = { value: Promise<T>; maybe?: Promise<T> };
+            export type LazyNumber = Lazy<number>;
+            export type IsPromise = string extends Promise<any> ? "yes" : "no";
+            "#,
+        );
+        assert_eq!(
+            types["LazyNumber"],
+            object(vec![
+                ("value", NUMBER),
+                ("maybe", RSType::Option(Box::new(NUMBER)))
+            ])
+        );
+        assert_eq!(types["IsPromise"], string_literal("no"));
+    }
+
+    #[test]
+    fn template_literals_are_expanded() {
+        let types = resolve(
+            "template_literal",
+            r#"
+            type Size = "sm" | "lg";
+            type Side = "top" | "bottom";
+            export type Class = `btn-${Size}`;
+            export type Margin = `m${Side}-${Size}`;
+            "#,
+        );
+        assert_eq!(
+            types["Class"],
+            union(vec![string_literal("btn-sm"), string_literal("btn-lg")])
+        );
+        assert_eq!(
+            types["Margin"],
+            union(vec![
+                string_literal("mtop-sm"),
+                string_literal("mtop-lg"),
+                string_literal("mbottom-sm"),
+                string_literal("mbottom-lg"),
+            ])
+        );
+    }
+
+    #[test]
+    fn intersections_are_merged_or_narrowed() {
+        let types = resolve(
+            "intersection",
+            r#"
+            export type Merged = { a: string } & { b: number };
+            export type Narrowed = "a" & string;
+            export type Disjoint = string & number;
+            export type Nullable = { a: string } & null;
+            export type Overlapping = { a: string | number } & { a: string; b?: boolean };
+            "#,
+        );
+        assert_eq!(types["Merged"], object(vec![("a", STRING), ("b", NUMBER)]));
+        assert_eq!(types["Narrowed"], string_literal("a"));
+        assert_eq!(types["Disjoint"], RSType::Never);
+        assert_eq!(types["Nullable"], RSType::Never);
+        assert_eq!(
+            types["Overlapping"],
+            object(vec![("a", STRING), ("b", option(BOOLEAN))])
+        );
+    }
+
+    #[test]
+    fn flattened_intersections_leave_named_fields_out_of_the_inline_struct() {
+        let fixture = Fixture::new(
+            "intersection_flatten",
+            &[(
+                "index.ts",
+                r#"
+                interface Base { id: string; kind: string }
+                export type Extended = Base & { kind: "user"; name: string };
+                "#,
+            )],
+        );
+        let options = TypeScriptOptions {
+            intersection_style: IntersectionStyle::Flatten,
+            ..TypeScriptOptions::default()
+        };
+        let (builder, result) = fixture.visit(&["index.ts"], options);
+        result.expect("the module is converted");
+        let index = fixture.path("index.ts");
+        assert_eq!(
+            types(&builder, &index)["Extended"],
+            RSType::Flatten(vec![
+                resolved("Base", &index),
+                object(vec![("name", STRING)]),
+            ])
+        );
+        assert!(warnings(&builder).is_empty());
+    }
+
+    #[test]
+    fn conflicting_fields_fail_in_strict_mode() {
+        let fixture = Fixture::new(
+            "intersection_conflict",
+            &[(
+                "index.ts",
+                "export type Conflict = { a: string } & { a: number };\n",
+            )],
+        );
+        let builder = fixture.convert("index.ts");
+        assert_eq!(
+            types(&builder, &fixture.path("index.ts"))["Conflict"],
+            object(vec![("a", STRING)])
+        );
+        assert_eq!(
+            warnings(&builder),
+            vec![
+                "`TSIntersectionType` dropped: conflicting type of the field `a` in later members"
+            ]
+        );
+
+        let options = TypeScriptOptions {
+            strict: true,
+            ..TypeScriptOptions::default()
+        };
+        let (_, result) = fixture.visit(&["index.ts"], options);
+        let error = result.expect_err("the conversion fails");
+        assert!(error.contains("conflicting type of the field `a`"));
+    }
+
+    #[test]
+    fn branded_types_are_converted_to_newtypes() {
+        let fixture = Fixture::new(
+            "brands",
+            &[(
+                "index.ts",
+                r#"
+                declare const brand: unique symbol;
+                type Brand<T, B> = T & { readonly __brand: B };
+                export type Email = string & { readonly __brand: "EmailAddress" };
+                export type OrderId = Brand<number, "OrderId">;
+                export type UserId = string & { [brand]: "UserId" };
+                export type Tagged = number & { readonly __tag: unique symbol };
+                export interface Branded { [brand]: "x"; a: string }
+                "#,
+            )],
+        );
+        let builder = fixture.convert("index.ts");
+        let types = types(&builder, &fixture.path("index.ts"));
+        assert_eq!(
+            types["Email"],
+            RSType::Newtype("Email".to_string(), Box::new(STRING))
+        );
+        assert_eq!(
+            types["OrderId"],
+            RSType::Newtype("OrderId".to_string(), Box::new(NUMBER))
+        );
+        assert_eq!(
+            types["UserId"],
+            RSType::Newtype("UserId".to_string(), Box::new(STRING))
+        );
+        assert_eq!(
+            types["Tagged"],
+            RSType::Newtype("Tagged".to_string(), Box::new(NUMBER))
+        );
+        // Brand fields are dropped from other types.
+        assert_eq!(types["Branded"], object(vec![("a", STRING)]));
+        assert_eq!(
+            warnings(&builder),
+            vec![
+                "`TSTypeOperator` is not implemented",
+                "`TSPropertySignature` dropped: field `[brand]`, which only brands an intersection",
+            ]
+        );
+    }
+}
@@ -5,11 +5,18 @@ use std::{
 };
 
 use codegen::Scope;
+use oxc_ast::{ast::Program, Trivias};
+use oxc_diagnostics::OxcDiagnostic;
 use oxc_resolver::Resolver;
+use oxc_span::{GetSpan, Span};
+use tracing::debug;
 
-use crate::{hashable_set::HashableSet, rs_types::RSTypeMap};
+use crate::{
+    hashable_set::HashableSet,
+    rs_types::{RSType, RSTypeMap},
+};
 
-use super::options::TypeScriptOptions;
+use super::{module::Module, options::TypeScriptOptions, warnings::ConversionWarning};
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum OriginalName {
@@ -22,14 +29,20 @@ pub enum OriginalName {
     /// import local from "source"
     Default,
     /// import * as local from "source"
+    /// import local = require("source")
     Namespace,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct TypeMapping {
     /// The path to the module where the type is originally defined.
-    /// Some(PathBuf) if the type is imported or re-exported, and None if it's a local type.
+    /// Some(PathBuf) if the type is imported or re-exported, and None if it's a local type
+    /// or the module specifier could not be resolved.
     pub original_module: Option<PathBuf>,
+    /// The module specifier as written in the import/export declaration, if any.
+    /// Types from unresolvable specifiers stay unresolved and keep the specifier
+    /// so they can be mapped to external Rust types.
+    pub module_specifier: Option<String>,
     /// The original name of the type in its original module.
     /// Some(String) for named imports/exports and None for default or namespace imports.
     pub original_name: OriginalName,
@@ -47,33 +60,135 @@ pub(crate) struct TypeScriptToRustVisitor {
     pub(super) resolver: Resolver,
     /// The codegen scope used to generate Rust code.
     pub(super) scope: codegen::Scope,
-    /// The type map used to store the types defined in this module.
-    pub(super) types: RSTypeMap,
-    /// The type mappings used to store the types imported/exported from other modules.
-    pub(super) type_mappings: HashMap<String, TypeMapping>,
+    /// The types and import/export edges collected from this module.
+    pub(super) module: Module,
+    /// The names of the enclosing namespaces of the node being visited.
+    pub(super) namespace: Vec<String>,
+    /// Whether the node being visited is in a `declare global` block or a script declaration file.
+    pub(super) global: bool,
+    /// The type parameters of the generic type alias being visited.
+    pub(super) type_parameters: Vec<String>,
+    /// The resolved paths of the modules this module depends on,
+    /// with the span of the first specifier resolved to each.
+    pub(super) dependencies: Vec<(PathBuf, Span)>,
     /// The source text of the current module (for debugging unimplemented types).
     pub(super) source_text: String,
+    /// The offset of the type expression being converted in the module source, if it is
+    /// parsed on its own, e.g. from a JSDoc comment. Added to the spans of its nodes.
+    pub(super) span_offset: u32,
     /// The options used to configure the TypeScript to Rust conversion.
     pub(super) options: TypeScriptOptions,
+    /// Recoverable problems found while visiting this module.
+    pub(super) diagnostics: Vec<OxcDiagnostic>,
+    /// Lossy or unimplemented conversions found while visiting this module.
+    pub(super) warnings: Vec<ConversionWarning>,
 }
 
 impl TypeScriptToRustVisitor {
-    pub(super) fn resolve_module(&mut self, specifier: &str) -> PathBuf {
-        println!("resolve_module: {:?}", specifier);
+    /// Resolves a module specifier relative to the current module.
+    /// Like tsc, falls back to the `@types` package of a package without typings.
+    /// Returns None and records a diagnostic if the specifier cannot be resolved.
+    pub(super) fn resolve_module(&mut self, specifier: &str, span: Span) -> Option<PathBuf> {
+        debug!("resolve_module: {:?}", specifier);
+        let span = self.module_span(span);
         let current_dir = self
             .path
             .parent()
             .expect("Failed to get current module directory");
-        let resolution = self
-            .resolver
-            .resolve(current_dir, specifier)
-            .unwrap_or_else(|_| {
-                panic!(
-                    "Failed to resolve module specifier: {} from path: {:?}",
-                    specifier, current_dir
-                )
-            });
-        resolution.full_path()
+        let resolution = match self.resolver.resolve(current_dir, specifier) {
+            Ok(resolution) if is_typescript_file(&resolution.full_path()) => Ok(resolution),
+            resolution => match types_package_specifier(specifier) {
+                Some(types_specifier) => {
+                    debug!("resolve_module: {:?}", types_specifier);
+                    self.resolver
+                        .resolve(current_dir, &types_specifier)
+                        .or(resolution)
+                }
+                None => resolution,
+            },
+        };
+        match resolution {
+            Ok(resolution) => {
+                let path = resolution.full_path();
+                if !self
+                    .dependencies
+                    .iter()
+                    .any(|(dependency, _)| dependency == &path)
+                {
+                    self.dependencies.push((path.clone(), span));
+                }
+                Some(path)
+            }
+            Err(error) => {
+                self.diagnostics.push(
+                    OxcDiagnostic::warn(format!(
+                        "Failed to resolve module specifier `{}` from {:?}: {}",
+                        specifier, current_dir, error
+                    ))
+                    .with_label(span.label("imported here"))
+                    .with_help("Types from this module will be left unresolved."),
+                );
+                None
+            }
+        }
+    }
+
+    /// Follows the `/// <reference path="..." />` and `/// <reference types="..." />`
+    /// directives at the top of this module, which bring global declarations into scope.
+    pub(super) fn visit_reference_directives(&mut self, program: &Program, trivias: &Trivias) {
+        // Directives are only valid before the first statement.
+        let header_end = program
+            .body
+            .first()
+            .map_or(program.span.end, |statement| statement.span().start);
+        let comments = trivias
+            .comments()
+            .filter(|comment| comment.is_line())
+            .take_while(|comment| comment.span.end <= header_end);
+        for comment in comments {
+            let text = comment.span.source_text(&self.source_text).to_string();
+            let Some((kind, value)) = text.strip_prefix('/').and_then(reference_directive) else {
+                continue;
+            };
+            match kind {
+                "path" if value.starts_with(['.', '/']) => {
+                    self.resolve_module(value, comment.span);
+                }
+                "path" => {
+                    self.resolve_module(&format!("./{}", value), comment.span);
+                }
+                "types" => {
+                    self.resolve_module(value, comment.span);
+                }
+                _ => debug!("ignoring reference directive: {}={:?}", kind, value),
+            }
+        }
+    }
+
+    /// Returns the span in the module source of a node of the type expression being converted.
+    pub(super) fn module_span(&self, span: Span) -> Span {
+        Span::new(
+            span.start.wrapping_add(self.span_offset),
+            span.end.wrapping_add(self.span_offset),
+        )
+    }
+
+    /// Adds a type declared in this module, or in the global scope (see `global`).
+    pub(super) fn declare_type(&mut self, name: String, rs_type: RSType) {
+        match self.global {
+            true => self.module.globals.insert(name, rs_type),
+            false => self.module.types.insert(name, rs_type),
+        };
+    }
+
+    /// Returns `name` qualified with the enclosing namespaces, e.g. `axe.Result`.
+    pub(super) fn qualified_name(&self, name: &str) -> String {
+        self.namespace
+            .iter()
+            .map(String::as_str)
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join(".")
     }
 
     pub fn new(
@@ -98,10 +213,47 @@ impl Default for TypeScriptToRustVisitor {
             path: PathBuf::default(),
             resolver: Resolver::default(),
             scope: Scope::new(),
-            types: RSTypeMap::default(),
-            type_mappings: HashMap::default(),
+            module: Module::default(),
+            namespace: Vec::default(),
+            global: false,
+            type_parameters: Vec::new(),
+            dependencies: Vec::default(),
             source_text: String::default(),
+            span_offset: 0,
             options: TypeScriptOptions::default(),
+            diagnostics: Vec::default(),
+            warnings: Vec::default(),
         }
     }
 }
+
+/// Parses a triple-slash directive like `<reference path="./globals.d.ts" />`
+/// into the name and value of its attribute.
+fn reference_directive(text: &str) -> Option<(&str, &str)> {
+    let attributes = text.trim().strip_prefix("<reference")?.strip_suffix("/>")?;
+    let (name, value) = attributes.split_once('=')?;
+    let value = value.trim_start();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = value[1..].split(quote).next()?;
+    Some((name.trim(), value))
+}
+
+/// Returns the specifier of the `@types` package for a bare module specifier,
+/// e.g. `@types/node/fs` for `node/fs` and `@types/babel__core` for `@babel/core`.
+fn types_package_specifier(specifier: &str) -> Option<String> {
+    if specifier.starts_with(['.', '/']) || specifier.starts_with("@types/") {
+        return None;
+    }
+    let types_name = match specifier.strip_prefix('@') {
+        Some(scoped) => scoped.replacen('/', "__", 1),
+        None => specifier.to_string(),
+    };
+    Some(format!("@types/{}", types_name))
+}
+
+/// Returns true for TypeScript sources and declaration files.
+fn is_typescript_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| matches!(extension, "ts" | "tsx" | "mts" | "cts"))
+}
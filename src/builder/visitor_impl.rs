@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 use convert_case::{Case, Casing};
 use oxc_ast::{
@@ -8,6 +8,7 @@ use oxc_ast::{
 };
 use oxc_span::{Atom, Span};
 use oxc_syntax::scope::ScopeFlags;
+use tracing::debug;
 
 use crate::{
     builder::visitor::{OriginalName, TypeMapping},
@@ -16,130 +17,360 @@ use crate::{
     string_utils::StringUtils,
 };
 
-use super::{make_rs_type, TypeScriptToRustVisitor};
+use super::{
+    make_rs_type::make_field_type,
+    module::{StarExport, TypeParameter},
+    warnings::Fallback,
+    TypeScriptToRustVisitor,
+};
 
 impl<'a> Visit<'a> for TypeScriptToRustVisitor {
+    fn visit_program(&mut self, it: &ast::Program<'a>) {
+        // Declaration files without imports or exports are scripts that declare global types.
+        if it.source_type.is_typescript_definition() && !is_module(it) {
+            debug!("script declaration file: {:?}", self.path);
+            self.global = true;
+        }
+        walk::walk_program(self, it);
+    }
+
     fn visit_import_declaration(&mut self, it: &ast::ImportDeclaration<'a>) {
-        let module_specifier = self.resolve_module(&it.source.value);
-        if let Some(specs) = &it.specifiers {
-            for spec in specs {
-                let (imported_name, local_name) = match spec {
-                    ast::ImportDeclarationSpecifier::ImportSpecifier(spec) => {
-                        let imported_name = spec.imported.name().into_string();
-                        let local_name = spec.local.name.clone().into_string();
-
-                        println!(
-                            "import ({:?}) {} as {} from {:?}",
-                            spec.import_kind, imported_name, local_name, &module_specifier
-                        );
-
-                        (OriginalName::Named(imported_name), local_name)
-                    }
-                    ast::ImportDeclarationSpecifier::ImportDefaultSpecifier(spec) => {
-                        let local_name = spec.local.name.clone().into_string();
-
-                        println!("import {} from {:?}", local_name, &module_specifier);
-
-                        (OriginalName::Default, local_name)
-                    }
-                    ast::ImportDeclarationSpecifier::ImportNamespaceSpecifier(spec) => {
-                        let local_name = spec.local.name.clone().into_string();
-
-                        println!("import * as {} from {:?}", local_name, &module_specifier);
-
-                        (OriginalName::Namespace, local_name)
-                    }
-                };
+        let module_specifier = self.resolve_module(&it.source.value, it.source.span);
+        // Side-effect imports (`import './globals'`) don't bring any types into scope,
+        // but the module may still declare global types.
+        let Some(specs) = &it.specifiers else {
+            return;
+        };
+        for spec in specs {
+            let (imported_name, local_name) = match spec {
+                ast::ImportDeclarationSpecifier::ImportSpecifier(spec) => {
+                    let imported_name = spec.imported.name().into_string();
+                    let local_name = spec.local.name.clone().into_string();
 
-                let mapping = TypeMapping {
-                    original_module: Some(module_specifier.clone()),
-                    original_name: imported_name,
-                    local_name: local_name.clone(),
-                    public_name: local_name.clone(),
-                };
+                    debug!(
+                        "import ({:?}) {} as {} from {:?}",
+                        spec.import_kind, imported_name, local_name, &module_specifier
+                    );
 
-                self.type_mappings.insert(local_name.clone(), mapping);
-            }
+                    (OriginalName::Named(imported_name), local_name)
+                }
+                ast::ImportDeclarationSpecifier::ImportDefaultSpecifier(spec) => {
+                    let local_name = spec.local.name.clone().into_string();
+
+                    debug!("import {} from {:?}", local_name, &module_specifier);
+
+                    (OriginalName::Default, local_name)
+                }
+                ast::ImportDeclarationSpecifier::ImportNamespaceSpecifier(spec) => {
+                    let local_name = spec.local.name.clone().into_string();
+
+                    debug!("import * as {} from {:?}", local_name, &module_specifier);
+
+                    (OriginalName::Namespace, local_name)
+                }
+            };
+
+            let mapping = TypeMapping {
+                original_module: module_specifier.clone(),
+                module_specifier: Some(it.source.value.to_string()),
+                original_name: imported_name,
+                local_name: local_name.clone(),
+                public_name: local_name.clone(),
+            };
+
+            self.module.imports.insert(local_name.clone(), mapping);
         }
         walk::walk_import_declaration(self, it);
     }
 
     fn visit_export_named_declaration(&mut self, it: &ast::ExportNamedDeclaration<'a>) {
+        let module_specifier = match &it.source {
+            Some(source) if !it.specifiers.is_empty() => {
+                self.resolve_module(&source.value, source.span)
+            }
+            _ => None,
+        };
+
         for spec in &it.specifiers {
             let exported_name = spec.exported.name().into_string();
             let local_name = spec.local.name().into_string();
-            let module_specifier = it.source.clone().map(|s| self.resolve_module(&s.value));
 
-            println!(
+            debug!(
                 "export {} as {} from {:?}",
                 exported_name, local_name, &module_specifier
             );
 
             let mapping = TypeMapping {
-                original_module: module_specifier,
+                original_module: module_specifier.clone(),
+                module_specifier: it.source.as_ref().map(|s| s.value.to_string()),
                 original_name: OriginalName::Named(local_name.clone()),
                 local_name: local_name.clone(),
-                public_name: exported_name,
+                public_name: exported_name.clone(),
             };
 
-            self.type_mappings.insert(local_name, mapping);
+            self.module.exports.insert(exported_name, mapping);
+        }
+
+        // `export interface Foo {}`, `export import foo = require('foo')`, ...
+        let declaration_name = match &it.declaration {
+            Some(ast::Declaration::TSTypeAliasDeclaration(declaration)) => {
+                Some(declaration.id.name.as_str())
+            }
+            Some(ast::Declaration::TSInterfaceDeclaration(declaration)) => {
+                Some(declaration.id.name.as_str())
+            }
+            Some(ast::Declaration::TSImportEqualsDeclaration(declaration)) => {
+                Some(declaration.id.name.as_str())
+            }
+            Some(ast::Declaration::TSModuleDeclaration(declaration)) => match &declaration.id {
+                ast::TSModuleDeclarationName::Identifier(id) => Some(id.name.as_str()),
+                ast::TSModuleDeclarationName::StringLiteral(_) => None,
+            },
+            _ => None,
+        };
+        if let (Some(name), true) = (declaration_name, self.namespace.is_empty() && !self.global) {
+            let name = name.to_string();
+            let mapping = TypeMapping {
+                original_module: None,
+                module_specifier: None,
+                original_name: OriginalName::Named(name.clone()),
+                local_name: name.clone(),
+                public_name: name.clone(),
+            };
+            self.module.exports.insert(name, mapping);
         }
 
         walk::walk_export_named_declaration(self, it);
     }
 
-    fn visit_ts_type_alias_declaration(&mut self, it: &ast::TSTypeAliasDeclaration<'a>) {
-        let type_name = it.id.name.to_string();
-        let rs_type = make_rs_type(&it.type_annotation, &self.source_text);
-        // println!("\nTYPE: {}: {:#?}", type_name, rs_type);
-        self.types.insert(type_name, rs_type);
+    fn visit_export_default_declaration(&mut self, it: &ast::ExportDefaultDeclaration<'a>) {
+        // `export default interface Foo {}`, `export default Foo`, `export default NS.Foo`
+        let local_name = match &it.declaration {
+            ast::ExportDefaultDeclarationKind::TSInterfaceDeclaration(declaration) => {
+                Some(declaration.id.name.to_string())
+            }
+            ast::ExportDefaultDeclarationKind::ClassDeclaration(class) => {
+                class.id.as_ref().map(|id| id.name.to_string())
+            }
+            ast::ExportDefaultDeclarationKind::FunctionDeclaration(_) => None,
+            declaration => declaration.as_expression().and_then(entity_name),
+        };
+
+        match local_name {
+            Some(local_name) => {
+                debug!("export default {}", local_name);
+
+                let mapping = TypeMapping {
+                    original_module: None,
+                    module_specifier: None,
+                    original_name: OriginalName::Named(local_name.clone()),
+                    local_name,
+                    public_name: "default".to_string(),
+                };
+                self.module.exports.insert("default".to_string(), mapping);
+            }
+            None => debug!("export default of a value or anonymous declaration"),
+        }
+
+        walk::walk_export_default_declaration(self, it);
     }
 
-    fn visit_ts_interface_declaration(&mut self, it: &ast::TSInterfaceDeclaration<'a>) {
-        let interface_name = it.id.name.to_string();
-        println!("\nINTERFACE: {}", &interface_name);
-        let mut fields: HashMap<String, RSType> = HashMap::new();
-        for member in &it.body.body {
-            let ast::TSSignature::TSPropertySignature(property) = member else {
-                continue;
-            };
-            if property.computed {
-                continue;
+    fn visit_export_all_declaration(&mut self, it: &ast::ExportAllDeclaration<'a>) {
+        let module_specifier = self.resolve_module(&it.source.value, it.source.span);
+
+        match &it.exported {
+            // `export * as ns from "source"` re-exports the module as a namespace.
+            Some(exported) => {
+                let exported_name = exported.name().into_string();
+
+                debug!("export * as {} from {:?}", exported_name, &module_specifier);
+
+                let mapping = TypeMapping {
+                    original_module: module_specifier,
+                    module_specifier: Some(it.source.value.to_string()),
+                    original_name: OriginalName::Namespace,
+                    local_name: exported_name.clone(),
+                    public_name: exported_name.clone(),
+                };
+                self.module.exports.insert(exported_name, mapping);
             }
-            let Some(field_name) = property.key.name() else {
+            // `export * from "source"` is expanded when linking the modules.
+            None => {
+                debug!("export * from {:?}", &module_specifier);
+
+                self.module.star_exports.push(StarExport {
+                    original_module: module_specifier,
+                    module_specifier: it.source.value.to_string(),
+                    span: it.span,
+                });
+            }
+        }
+    }
+
+    fn visit_ts_module_declaration(&mut self, it: &ast::TSModuleDeclaration<'a>) {
+        match &it.id {
+            ast::TSModuleDeclarationName::Identifier(id) if !it.kind.is_global() => {
+                self.namespace.push(id.name.to_string());
+                walk::walk_ts_module_declaration(self, it);
+                self.namespace.pop();
+            }
+            // `declare global { ... }` adds its declarations to the global scope.
+            _ if it.kind.is_global() => {
+                let namespace = std::mem::take(&mut self.namespace);
+                let global = std::mem::replace(&mut self.global, true);
+                walk::walk_ts_module_declaration(self, it);
+                self.global = global;
+                self.namespace = namespace;
+            }
+            _ => walk::walk_ts_module_declaration(self, it),
+        }
+    }
+
+    fn visit_ts_export_assignment(&mut self, it: &ast::TSExportAssignment<'a>) {
+        // `export = axe`: the members of `axe` are the exports of this module.
+        match entity_name(&it.expression) {
+            Some(entity) => {
+                debug!("export = {}", entity);
+                self.module.export_assignment = Some(entity);
+            }
+            None => self.warn(
+                it,
+                it.span,
+                Fallback::Dropped("`export =` of an expression".to_string()),
+            ),
+        }
+    }
+
+    fn visit_ts_namespace_export_declaration(
+        &mut self,
+        it: &ast::TSNamespaceExportDeclaration<'a>,
+    ) {
+        // `export as namespace axe` (UMD) makes this module available as the global `axe`.
+        debug!("export as namespace {}", it.id.name);
+        self.module.namespace_export = Some(it.id.name.to_string());
+    }
+
+    fn visit_ts_import_equals_declaration(&mut self, it: &ast::TSImportEqualsDeclaration<'a>) {
+        let local_name = it.id.name.to_string();
+        match &it.module_reference {
+            ast::TSModuleReference::ExternalModuleReference(reference) => {
+                let source = &reference.expression;
+                let module_specifier = self.resolve_module(&source.value, source.span);
+
+                debug!("import {} = require({:?})", local_name, &module_specifier);
+
+                let mapping = TypeMapping {
+                    original_module: module_specifier,
+                    module_specifier: Some(source.value.to_string()),
+                    original_name: OriginalName::Namespace,
+                    local_name: local_name.clone(),
+                    public_name: local_name.clone(),
+                };
+                self.module.imports.insert(local_name, mapping);
+            }
+            module_reference => {
+                let entity = match module_reference {
+                    ast::TSModuleReference::IdentifierReference(id) => id.name.to_string(),
+                    ast::TSModuleReference::QualifiedName(name) => name.to_string(),
+                    ast::TSModuleReference::ExternalModuleReference(_) => unreachable!(),
+                };
+
+                debug!("import {} = {}", local_name, entity);
+
+                let qualified_name = self.qualified_name(&local_name);
+                self.module.aliases.insert(qualified_name, entity);
+            }
+        }
+    }
+
+    fn visit_variable_declaration(&mut self, it: &ast::VariableDeclaration<'a>) {
+        for declarator in &it.declarations {
+            let ast::BindingPatternKind::BindingIdentifier(id) = &declarator.id.kind else {
                 continue;
             };
-            let ts_type = match &property.type_annotation {
-                Some(type_annotation) => &type_annotation.type_annotation,
-                None => continue,
+            // `const kind = "a"` has the literal type, `let kind = "a"` is widened.
+            let rs_type = match (&declarator.id.type_annotation, &declarator.init) {
+                (Some(type_annotation), _) => {
+                    Some(self.make_rs_type(&type_annotation.type_annotation))
+                }
+                (None, Some(init)) if it.kind == ast::VariableDeclarationKind::Const => {
+                    self.make_value_type(init)
+                }
+                _ => None,
             };
-            let rs_type = make_rs_type(ts_type, &self.source_text);
-            let rs_type = match rs_type {
-                RSType::Option(inner) => RSType::Option(inner),
-                RSType::Enum(rs_enum) => match property.optional || rs_enum.option {
-                    true => RSType::Option(Box::new(RSType::Enum(rs_enum))),
-                    false => RSType::Enum(rs_enum),
-                },
-                _ => match property.optional {
-                    true => RSType::Option(Box::new(rs_type)),
-                    false => rs_type,
-                },
+            let Some(rs_type) = rs_type else {
+                debug!("cannot infer the type of the value {}", id.name);
+                continue;
             };
+            let value_name = self.qualified_name(&id.name);
+            debug!("VALUE: {}", &value_name);
+            self.module.values.insert(value_name, rs_type);
+        }
+    }
 
-            if self.options.ignore_unimplemented {
-                if let RSType::Unimplemented(_, _) = rs_type {
-                    continue;
-                }
-            }
+    fn visit_function_body(&mut self, _it: &ast::FunctionBody<'a>) {
+        // Local variables of JavaScript functions are not visible to `typeof`.
+    }
 
-            fields.insert(field_name.to_string(), rs_type);
+    fn visit_ts_type_alias_declaration(&mut self, it: &ast::TSTypeAliasDeclaration<'a>) {
+        let type_name = self.qualified_name(&it.id.name);
+        let type_parameters: Vec<TypeParameter> = it
+            .type_parameters
+            .iter()
+            .flat_map(|declaration| &declaration.params)
+            .map(|parameter| TypeParameter {
+                name: parameter.name.name.to_string(),
+                default: parameter
+                    .default
+                    .as_ref()
+                    .map(|default| self.make_rs_type(default)),
+            })
+            .collect();
+        self.type_parameters = type_parameters.iter().map(|p| p.name.clone()).collect();
+        let rs_type = self.make_rs_type(&it.type_annotation);
+        self.type_parameters.clear();
+        if !type_parameters.is_empty() {
+            self.module
+                .type_parameters
+                .insert(type_name.clone(), type_parameters);
         }
+        // trace!("TYPE: {}: {:#?}", type_name, rs_type);
+        self.declare_type(type_name, rs_type);
+    }
 
-        if fields.is_empty() {
+    fn visit_ts_interface_declaration(&mut self, it: &ast::TSInterfaceDeclaration<'a>) {
+        let interface_name = self.qualified_name(&it.id.name);
+        debug!("INTERFACE: {}", &interface_name);
+        let rs_struct = self.make_struct_type(&interface_name, &it.body.body);
+        if rs_struct.fields.is_empty() {
             return;
         }
 
-        self.types
-            .insert(interface_name, RSType::Struct(RSStruct { fields }));
+        self.declare_type(interface_name, RSType::Struct(rs_struct));
+    }
+}
+
+/// Returns the dotted name of an identifier or static member expression, e.g. `axe.Result`.
+fn entity_name(expression: &ast::Expression) -> Option<String> {
+    match expression {
+        ast::Expression::Identifier(id) => Some(id.name.to_string()),
+        ast::Expression::StaticMemberExpression(member) => Some(format!(
+            "{}.{}",
+            entity_name(&member.object)?,
+            member.property.name
+        )),
+        ast::Expression::ParenthesizedExpression(expression) => entity_name(&expression.expression),
+        _ => None,
     }
 }
+
+/// Returns true if the program has an import or export, i.e. is not a script.
+fn is_module(program: &ast::Program) -> bool {
+    program.body.iter().any(|statement| match statement {
+        ast::Statement::TSImportEqualsDeclaration(declaration) => matches!(
+            declaration.module_reference,
+            ast::TSModuleReference::ExternalModuleReference(_)
+        ),
+        statement => statement.is_module_declaration(),
+    })
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use oxc_diagnostics::OxcDiagnostic;
+use oxc_span::Span;
+
+/// What the conversion did with a TypeScript construct it could not convert exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fallback {
+    /// The construct was replaced by a less precise Rust type.
+    Substituted(String),
+    /// The construct was kept as `RSType::Unimplemented`.
+    Unimplemented,
+    /// The construct was left out of the output, e.g. a skipped field or member.
+    Dropped(String),
+    /// The type operator could not be evaluated when linking, e.g. `keyof` of an unresolved type,
+    /// and was kept as is.
+    Unevaluated,
+}
+
+/// A lossy or unimplemented conversion of a TypeScript construct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionWarning {
+    /// The path to the module containing the construct.
+    pub module_path: PathBuf,
+    /// The span of the construct in the module source.
+    pub span: Span,
+    /// The kind of TypeScript construct, e.g. `TSMappedType`.
+    pub construct: String,
+    /// What was substituted or dropped.
+    pub fallback: Fallback,
+}
+
+impl ConversionWarning {
+    pub fn to_diagnostic(&self) -> OxcDiagnostic {
+        let (message, label) = match &self.fallback {
+            Fallback::Substituted(rust_type) => (
+                format!("`{}` converted to `{}`", self.construct, rust_type),
+                format!("converted to `{}`", rust_type),
+            ),
+            Fallback::Unimplemented => (
+                format!("`{}` is not implemented", self.construct),
+                "unimplemented".to_string(),
+            ),
+            Fallback::Dropped(what) => (
+                format!("`{}` dropped: {}", self.construct, what),
+                "dropped".to_string(),
+            ),
+            Fallback::Unevaluated => (
+                format!("`{}` could not be evaluated", self.construct),
+                "not evaluated".to_string(),
+            ),
+        };
+        OxcDiagnostic::warn(message).with_label(self.span.label(label))
+    }
+}
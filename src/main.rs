@@ -1,13 +1,21 @@
 #![allow(unused, dead_code)]
 
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use builder::{options::TypeScriptOptions, TypeScriptToRustBuilder};
+use builder::{
+    errors::render_diagnostic,
+    options::{IntersectionStyle, TypeScriptOptions},
+    TypeScriptToRustBuilder,
+};
 use oxc_allocator::Allocator;
 use oxc_ast::Visit;
 use oxc_parser::{ParseOptions, Parser};
 use oxc_resolver::{ResolveOptions, Resolver};
 use oxc_span::SourceType;
+use tracing::Level;
 
 mod builder;
 mod hashable_set;
@@ -15,12 +23,66 @@ mod rs_types;
 mod string_utils;
 
 fn main() -> Result<(), String> {
-    let entrypoint = String::from("examples/axe/axe-types.ts");
-    let path = Path::new(&entrypoint).canonicalize().unwrap();
+    let mut args = pico_args::Arguments::from_env();
+    let strict = args.contains("--strict");
+    let flatten_intersections = args.contains("--flatten-intersections");
+    let quiet = args.contains(["-q", "--quiet"]);
+    let mut verbosity = 0;
+    while args.contains(["-v", "--verbose"]) {
+        verbosity += 1;
+    }
+    let tsconfig: Option<PathBuf> = args
+        .opt_value_from_str("--tsconfig")
+        .map_err(|error| error.to_string())?;
+    let conditions: Vec<String> = args
+        .values_from_str("--condition")
+        .map_err(|error| error.to_string())?;
+    let mut entrypoints: Vec<String> = Vec::new();
+    while let Some(entrypoint) = args
+        .opt_free_from_str()
+        .map_err(|error: pico_args::Error| error.to_string())?
+    {
+        entrypoints.push(entrypoint);
+    }
+    if entrypoints.is_empty() {
+        entrypoints.push(String::from("examples/axe/axe-types.ts"));
+    }
+    init_logging(quiet, verbosity);
 
-    let options = TypeScriptOptions::default();
+    let paths = entrypoints
+        .iter()
+        .map(|entrypoint| {
+            Path::new(entrypoint)
+                .canonicalize()
+                .map_err(|error| format!("{}: {}", entrypoint, error))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let intersection_style = match flatten_intersections {
+        true => IntersectionStyle::Flatten,
+        false => IntersectionStyle::Merge,
+    };
+    let mut options = TypeScriptOptions {
+        strict,
+        tsconfig,
+        intersection_style,
+        ..TypeScriptOptions::default()
+    };
+    // Additional package.json `exports` conditions, like `customConditions` in tsconfig.json.
+    options.resolve_options.condition_names.extend(conditions);
     let mut builder = TypeScriptToRustBuilder::new(options);
-    builder.visit_module(path);
+    let result = builder.visit_modules(paths);
+
+    if !quiet {
+        for diagnostic in builder.diagnostics() {
+            eprint!("{}", render_diagnostic(diagnostic));
+        }
+    }
+
+    if let Err(error) = result {
+        eprint!("{}", error);
+        std::process::exit(1);
+    }
 
     // let references = resolve_references(&mut visitor.type_map);
 
@@ -32,3 +94,37 @@ fn main() -> Result<(), String> {
 
     Ok(())
 }
+
+/// Sends logs to stderr so that stdout only contains generated output.
+fn init_logging(quiet: bool, verbosity: usize) {
+    tracing_subscriber::fmt()
+        .with_max_level(log_level(quiet, verbosity))
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// `--quiet` only shows errors, and each `-v` raises the level from warnings
+/// to info, debug and trace.
+fn log_level(quiet: bool, verbosity: usize) -> Level {
+    match (quiet, verbosity) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_flags_raise_the_log_level() {
+        assert_eq!(log_level(false, 0), Level::WARN);
+        assert_eq!(log_level(false, 1), Level::INFO);
+        assert_eq!(log_level(false, 2), Level::DEBUG);
+        assert_eq!(log_level(false, 3), Level::TRACE);
+        assert_eq!(log_level(true, 2), Level::ERROR);
+    }
+}
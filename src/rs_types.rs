@@ -1,5 +1,6 @@
 use std::{collections::HashMap, path::PathBuf};
 
+use oxc_span::Span;
 use serde::{Deserialize, Serialize};
 
 pub(crate) type RSTypeMap = HashMap<String, RSType>;
@@ -8,6 +9,7 @@ pub(crate) type RSTypeMap = HashMap<String, RSType>;
 pub(crate) enum RSPrimitive {
     String,
     I32,
+    I64,
     I128,
     Bool,
     F64,
@@ -19,6 +21,7 @@ impl RSPrimitive {
         match self {
             RSPrimitive::String => "String".to_string(),
             RSPrimitive::I32 => "i32".to_string(),
+            RSPrimitive::I64 => "i64".to_string(),
             RSPrimitive::I128 => "i128".to_string(),
             RSPrimitive::Bool => "bool".to_string(),
             RSPrimitive::F64 => "f64".to_string(),
@@ -37,6 +40,88 @@ pub(crate) struct RSStruct {
     pub(crate) fields: HashMap<String, RSType>,
 }
 
+/// A mapped type, e.g. `{ [K in keyof T]?: T[K] }`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct RSMappedType {
+    /// The name of the key type parameter, e.g. `K`.
+    pub(crate) key_name: String,
+    /// The keys the parameter ranges over, e.g. `keyof T`.
+    pub(crate) keys: Box<RSType>,
+    /// The type of the properties, in which the key type parameter is substituted with each key.
+    pub(crate) value: Box<RSType>,
+    /// Whether the properties are made optional (`?`, `+?`) or required (`-?`),
+    /// or None if their optionality is kept.
+    pub(crate) optional: Option<bool>,
+    /// The span of the mapped type in the module source.
+    #[serde(skip)]
+    pub(crate) span: Span,
+}
+
+/// A conditional type, e.g. `T extends Array<infer U> ? U : never`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct RSConditionalType {
+    pub(crate) check_type: Box<RSType>,
+    pub(crate) extends_type: Box<RSType>,
+    pub(crate) true_type: Box<RSType>,
+    pub(crate) false_type: Box<RSType>,
+    /// The type parameter checked by a distributive conditional type, e.g. `T` in
+    /// `T extends U ? never : T`. It is substituted in the branches with each member of a union.
+    pub(crate) distributive_parameter: Option<String>,
+    /// The span of the conditional type in the module source.
+    #[serde(skip)]
+    pub(crate) span: Span,
+}
+
+/// A template literal type, e.g. `data-${string}`: the literal parts around the types.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct RSTemplateLiteral {
+    /// The literal parts, one more than the types.
+    pub(crate) quasis: Vec<String>,
+    pub(crate) types: Vec<RSType>,
+}
+
+impl RSTemplateLiteral {
+    /// Returns true if `value` matches the template, i.e. it can be converted to the newtype.
+    /// Unions of literals match one of the literals, `number` and `boolean` their string forms,
+    /// and other types any string.
+    pub(crate) fn matches(&self, value: &str) -> bool {
+        fn matches_from(quasis: &[String], types: &[RSType], value: &str) -> bool {
+            let Some(rest) = value.strip_prefix(quasis[0].as_str()) else {
+                return false;
+            };
+            let Some((rs_type, types)) = types.split_first() else {
+                return rest.is_empty();
+            };
+            (0..=rest.len())
+                .filter(|&end| rest.is_char_boundary(end))
+                .any(|end| {
+                    matches_type(rs_type, &rest[..end])
+                        && matches_from(&quasis[1..], types, &rest[end..])
+                })
+        }
+
+        fn matches_type(rs_type: &RSType, text: &str) -> bool {
+            match rs_type {
+                RSType::EnumVariant(RSEnumVariant::StringLiteral(literal))
+                | RSType::EnumVariant(RSEnumVariant::NumericLiteral(literal)) => literal == text,
+                RSType::EnumVariant(RSEnumVariant::BooleanLiteral(literal)) => {
+                    literal.to_string() == text
+                }
+                RSType::Enum(RSEnum { variants, .. }) => {
+                    variants.iter().any(|variant| matches_type(variant, text))
+                }
+                RSType::Primitive(RSPrimitive::Bool) => text == "true" || text == "false",
+                RSType::Primitive(RSPrimitive::String) => true,
+                RSType::Primitive(_) => !text.trim().is_empty() && text.parse::<f64>().is_ok(),
+                RSType::TemplateLiteral(template) => template.matches(text),
+                _ => true,
+            }
+        }
+
+        matches_from(&self.quasis, &self.types, value)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub(crate) enum RSEnumVariant {
     RSType(Box<RSType>),
@@ -44,7 +129,8 @@ pub(crate) enum RSEnumVariant {
     BooleanLiteral(bool),
     NullLiteral,
     NumericLiteral(String),
-    Unimplemented(String, String),
+    /// The TypeScript construct, its source text and its span in the module source.
+    Unimplemented(String, String, #[serde(skip)] Span),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -53,12 +139,34 @@ pub(crate) enum RSReference {
         name: String,
         module_specifier: Option<String>,
     },
+    /// A type exported from a module, e.g. `import("./foo").Bar`,
+    /// that is looked up in the exports of the module when linking.
+    Imported {
+        name: String,
+        module_path: PathBuf,
+    },
     Resolved {
         name: String,
         module_path: PathBuf,
     },
 }
 
+impl RSReference {
+    /// The namespace-qualified name of the referenced type, e.g. `axe.Result`.
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            RSReference::Unresolved { name, .. } => name,
+            RSReference::Imported { name, .. } => name,
+            RSReference::Resolved { name, .. } => name,
+        }
+    }
+
+    /// The Rust path of the referenced type, e.g. `axe::Result` for `axe.Result`.
+    pub(crate) fn rust_path(&self) -> String {
+        self.name().split('.').collect::<Vec<_>>().join("::")
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub(crate) enum RSType {
     Primitive(RSPrimitive),
@@ -67,11 +175,56 @@ pub(crate) enum RSType {
     Struct(RSStruct),
     EnumVariant(RSEnumVariant),
     Vec(Box<RSType>),
+    /// A tuple type with a fixed number of elements, e.g. `[string, number]`.
+    Tuple(Vec<RSType>),
+    /// A promise, e.g. `Promise<User>`, converted to the type of its value in fields.
+    Promise(Box<RSType>),
+    /// A map of keys to values, e.g. `{ [key: string]: number }`.
+    HashMap(Box<RSType>, Box<RSType>),
     Option(Box<RSType>),
     JSONValue,
+    /// The `object` type, of any non-primitive value, converted to `serde_json::Value`.
+    Object,
     NullOrUndefined,
     Unit,
-    Unimplemented(String, String),
+    /// The `never` type, e.g. of an empty union or an intersection of disjoint types.
+    Never,
+    /// The type of a value, e.g. `typeof config`, replaced with the type of the value when linking,
+    /// with its span in the module source.
+    TypeQuery(RSReference, #[serde(skip)] Span),
+    /// The property names of a type, e.g. `keyof Options`, evaluated to an enum when linking,
+    /// with its span in the module source.
+    KeyOf(Box<RSType>, #[serde(skip)] Span),
+    /// The type of a property or element of a type, e.g. `Options['name']` or `Selectors[0]`,
+    /// evaluated when linking, with its span in the module source.
+    IndexedAccess(Box<RSType>, Box<RSType>, #[serde(skip)] Span),
+    /// A mapped type, evaluated to a struct (finite keys) or a map (`string` keys) when linking.
+    Mapped(RSMappedType),
+    /// A generic type with type arguments, e.g. `Page<User>`, replaced with the declaration of
+    /// a generic type alias with the arguments substituted when linking, with its span in the
+    /// module source.
+    Instantiation(RSReference, Vec<RSType>, #[serde(skip)] Span),
+    /// A conditional type, evaluated to one of its branches when linking.
+    Conditional(RSConditionalType),
+    /// A type inferred in the `extends` clause of a conditional type, e.g. `infer U`.
+    Infer(String),
+    /// An intersection type, e.g. `A & B & { extra: string }`, merged into a struct
+    /// (or composed into a `Flatten` struct) when linking, with its span in the module source.
+    Intersection(Vec<RSType>, #[serde(skip)] Span),
+    /// A struct composed of the fields of its members with `#[serde(flatten)]`,
+    /// for intersections with `IntersectionStyle::Flatten`.
+    Flatten(Vec<RSType>),
+    /// A branded type, e.g. `string & { readonly __brand: "UserId" }`, converted to a
+    /// `#[serde(transparent)]` newtype named after the type alias declaring it, or else the brand.
+    Newtype(String, Box<RSType>),
+    /// The type of a field that can only brand a type, e.g. `[brand]: "UserId"` or
+    /// `__tag: unique symbol`, with its span in the module source. Dropped after linking.
+    Brand(Box<RSType>, #[serde(skip)] Span),
+    /// A template literal type, expanded to an enum of strings when linking if it has finitely
+    /// many expansions, or else converted to a `String` newtype validated with `matches`.
+    TemplateLiteral(RSTemplateLiteral),
+    /// The TypeScript construct, its source text and its span in the module source.
+    Unimplemented(String, String, #[serde(skip)] Span),
 }
 
 impl RSType {
@@ -79,16 +232,165 @@ impl RSType {
     pub(crate) fn name(&self) -> String {
         match self {
             RSType::Primitive(p) => p.name(),
-            RSType::Reference(r) => format!("REF<{:?}>", r),
+            RSType::Reference(r) => r.rust_path(),
             RSType::Enum(e) => format!("{:?}", e),
             RSType::Struct(s) => format!("{:?}", s),
             RSType::EnumVariant(v) => format!("{:?}", v),
             RSType::Vec(v) => format!("Vec<{}>", v.name()),
+            RSType::Promise(p) => format!("Promise<{}>", p.name()),
+            RSType::Tuple(elements) => format!(
+                "({})",
+                elements
+                    .iter()
+                    .map(RSType::name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            RSType::HashMap(k, v) => format!("HashMap<{}, {}>", k.name(), v.name()),
             RSType::Option(o) => format!("Option<{}>", o.name()),
-            RSType::JSONValue => "serde_json::Value".to_string(),
+            RSType::JSONValue | RSType::Object => "serde_json::Value".to_string(),
             RSType::NullOrUndefined => "Option<()>".to_string(),
             RSType::Unit => "()".to_string(),
-            RSType::Unimplemented(t, n) => format!("Unimplemented<{}, {}>", t, n),
+            RSType::Never => "!".to_string(),
+            RSType::TypeQuery(r, _) => format!("typeof {}", r.name()),
+            RSType::KeyOf(t, _) => format!("keyof {}", t.name()),
+            RSType::IndexedAccess(t, i, _) => format!("{}[{}]", t.name(), i.name()),
+            RSType::Mapped(m) => format!(
+                "{{ [{} in {}]: {} }}",
+                m.key_name,
+                m.keys.name(),
+                m.value.name()
+            ),
+            RSType::Instantiation(r, args, _) => format!(
+                "{}<{}>",
+                r.rust_path(),
+                args.iter().map(RSType::name).collect::<Vec<_>>().join(", ")
+            ),
+            RSType::Conditional(c) => format!(
+                "{} extends {} ? {} : {}",
+                c.check_type.name(),
+                c.extends_type.name(),
+                c.true_type.name(),
+                c.false_type.name()
+            ),
+            RSType::Infer(n) => format!("infer {}", n),
+            RSType::Intersection(members, _) => members
+                .iter()
+                .map(RSType::name)
+                .collect::<Vec<_>>()
+                .join(" & "),
+            RSType::Flatten(members) => format!(
+                "Flatten<{}>",
+                members
+                    .iter()
+                    .map(RSType::name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            RSType::Newtype(brand, inner) => format!("{}({})", brand, inner.name()),
+            RSType::Brand(inner, _) => format!("Brand<{}>", inner.name()),
+            RSType::TemplateLiteral(t) => {
+                let mut pattern = t.quasis[0].clone();
+                for (rs_type, quasi) in t.types.iter().zip(&t.quasis[1..]) {
+                    pattern.push_str(&format!("${{{}}}{}", rs_type.name(), quasi));
+                }
+                format!("`{}`", pattern)
+            }
+            RSType::Unimplemented(t, n, _) => format!("Unimplemented<{}, {}>", t, n),
+        }
+    }
+
+    /// Returns this type with the references to the type parameter `name` replaced,
+    /// e.g. `K` with `"a"` in `T[K]`.
+    pub(crate) fn substitute(&self, name: &str, replacement: &RSType) -> RSType {
+        let substitute = |rs_type: &RSType| Box::new(rs_type.substitute(name, replacement));
+        match self {
+            RSType::Reference(RSReference::Unresolved {
+                name: reference_name,
+                module_specifier: None,
+            }) if reference_name == name => replacement.clone(),
+            RSType::Enum(RSEnum { option, variants }) => RSType::Enum(RSEnum {
+                option: *option,
+                variants: variants
+                    .iter()
+                    .map(|variant| variant.substitute(name, replacement))
+                    .collect(),
+            }),
+            RSType::Struct(RSStruct { fields }) => RSType::Struct(RSStruct {
+                fields: fields
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (field_name.clone(), field_type.substitute(name, replacement))
+                    })
+                    .collect(),
+            }),
+            RSType::EnumVariant(RSEnumVariant::RSType(inner)) => {
+                RSType::EnumVariant(RSEnumVariant::RSType(substitute(inner)))
+            }
+            RSType::Vec(inner) => RSType::Vec(substitute(inner)),
+            RSType::Promise(inner) => RSType::Promise(substitute(inner)),
+            RSType::Tuple(elements) => RSType::Tuple(
+                elements
+                    .iter()
+                    .map(|element| element.substitute(name, replacement))
+                    .collect(),
+            ),
+            RSType::HashMap(key, value) => RSType::HashMap(substitute(key), substitute(value)),
+            RSType::Option(inner) => RSType::Option(substitute(inner)),
+            RSType::Newtype(brand, inner) => RSType::Newtype(brand.clone(), substitute(inner)),
+            RSType::Brand(inner, span) => RSType::Brand(substitute(inner), *span),
+            RSType::KeyOf(inner, span) => RSType::KeyOf(substitute(inner), *span),
+            RSType::IndexedAccess(object, index, span) => {
+                RSType::IndexedAccess(substitute(object), substitute(index), *span)
+            }
+            RSType::Mapped(mapped) => RSType::Mapped(RSMappedType {
+                keys: substitute(&mapped.keys),
+                // The key type parameter shadows a type parameter with the same name.
+                value: match mapped.key_name == name {
+                    true => mapped.value.clone(),
+                    false => substitute(&mapped.value),
+                },
+                ..mapped.clone()
+            }),
+            RSType::Instantiation(reference, arguments, span) => RSType::Instantiation(
+                reference.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| argument.substitute(name, replacement))
+                    .collect(),
+                *span,
+            ),
+            RSType::Intersection(members, span) => RSType::Intersection(
+                members
+                    .iter()
+                    .map(|member| member.substitute(name, replacement))
+                    .collect(),
+                *span,
+            ),
+            RSType::TemplateLiteral(template) => RSType::TemplateLiteral(RSTemplateLiteral {
+                quasis: template.quasis.clone(),
+                types: template
+                    .types
+                    .iter()
+                    .map(|rs_type| rs_type.substitute(name, replacement))
+                    .collect(),
+            }),
+            RSType::Conditional(conditional) => {
+                // The branches of a conditional type distributed over `name` are substituted
+                // with each member of the union when it is evaluated.
+                let branch = |rs_type: &RSType| match conditional.distributive_parameter {
+                    Some(ref parameter) if parameter == name => Box::new(rs_type.clone()),
+                    _ => substitute(rs_type),
+                };
+                RSType::Conditional(RSConditionalType {
+                    check_type: substitute(&conditional.check_type),
+                    extends_type: substitute(&conditional.extends_type),
+                    true_type: branch(&conditional.true_type),
+                    false_type: branch(&conditional.false_type),
+                    ..conditional.clone()
+                })
+            }
+            _ => self.clone(),
         }
     }
 }